@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use copter::{
+    broadphase::{update_broad_phase, BroadPhase},
+    components::{Copter, Obstacle},
+    constants::{COPTER_SIZE, COPTER_START_X, LAN_MAX_PLAYERS, OBSTACLE_HEIGHT, OBSTACLE_WIDTH},
+    enums::Player,
+};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+/*
+    Finding the obstacles a race of copters overlaps, by checking every
+    obstacle against every copter as collision detection used to, and by
+    asking the broad-phase for the ones around each copter first. Both
+    scroll the obstacles a little every tick, so that keeping the
+    broad-phase up to date is part of what is measured. Nothing is ever
+    hit, so that every copter keeps being checked.
+
+        cargo bench --bench collision
+*/
+
+const OBSTACLE_COUNTS: [usize; 4] = [10, 100, 1_000, 10_000];
+
+// Obstacles twice as far apart as they are wide, well above and below the copters.
+fn spawn_world(obstacles: usize) -> World {
+    let mut world = World::new();
+    world.init_resource::<BroadPhase>();
+
+    for player in 0..LAN_MAX_PLAYERS {
+        world.spawn((
+            Transform::from_xyz(COPTER_START_X, player as f32 * 10.0 - 40.0, 1.0),
+            Copter {
+                velocity: 0.0,
+                player: Player(player),
+                lift: false,
+                crash_cause: None,
+            },
+        ));
+    }
+    let spacing = OBSTACLE_WIDTH * 2.0;
+    let first_x = COPTER_START_X - spacing * obstacles as f32 * 0.5;
+    for i in 0..obstacles {
+        let y = if i % 2 == 0 { 300.0 } else { -300.0 };
+        world.spawn((
+            Sprite {
+                custom_size: Some(Vec2::new(OBSTACLE_WIDTH, OBSTACLE_HEIGHT)),
+                ..Default::default()
+            },
+            Transform::from_xyz(first_x + spacing * i as f32, y, 1.0),
+            Obstacle,
+        ));
+    }
+    world
+}
+
+// Back and forth, so that the copters stay amid the obstacles however long the benchmark runs.
+fn scroll(mut obstacle_query: Query<&mut Transform, With<Obstacle>>, mut left: Local<bool>) {
+    *left = !*left;
+    let distance = if *left { -10.0 } else { 10.0 };
+    for mut transform in obstacle_query.iter_mut() {
+        transform.translation.x += distance;
+    }
+}
+
+fn overlaps(copter: &Transform, obstacle: &Transform, obstacle_sprite: &Sprite) -> bool {
+    let obstacle_size = obstacle_sprite.custom_size.unwrap_or(Vec2::ONE);
+    let distance = (obstacle.translation - copter.translation).truncate().abs();
+    distance.x < (COPTER_SIZE.x + obstacle_size.x) * 0.5
+        && distance.y <= (COPTER_SIZE.y + obstacle_size.y) * 0.5
+}
+
+fn scan_every_obstacle(
+    copter_query: Query<&Transform, With<Copter>>,
+    obstacle_query: Query<(&Transform, &Sprite), With<Obstacle>>,
+) {
+    for copter in copter_query.iter() {
+        for (obstacle, sprite) in obstacle_query.iter() {
+            assert!(!overlaps(copter, obstacle, sprite));
+        }
+    }
+}
+
+fn scan_broad_phase(
+    broad_phase: Res<BroadPhase>,
+    copter_query: Query<&Transform, With<Copter>>,
+    obstacle_query: Query<(&Transform, &Sprite), With<Obstacle>>,
+) {
+    for copter in copter_query.iter() {
+        let candidates = broad_phase.candidates(
+            copter.translation.x - COPTER_SIZE.x * 0.5,
+            copter.translation.x + COPTER_SIZE.x * 0.5,
+        );
+        for (obstacle, sprite) in obstacle_query.iter_many(candidates) {
+            assert!(!overlaps(copter, obstacle, sprite));
+        }
+    }
+}
+
+fn collision_lookups(c: &mut Criterion) {
+    let mut group = c.benchmark_group("collision_lookups");
+
+    for obstacles in OBSTACLE_COUNTS {
+        let mut world = spawn_world(obstacles);
+        let mut schedule = Schedule::default();
+        schedule.add_systems((scroll, scan_every_obstacle).chain());
+        group.bench_function(BenchmarkId::new("every_obstacle", obstacles), |b| {
+            b.iter(|| schedule.run(&mut world))
+        });
+
+        let mut world = spawn_world(obstacles);
+        let mut schedule = Schedule::default();
+        schedule.add_systems((scroll, update_broad_phase, scan_broad_phase).chain());
+        group.bench_function(BenchmarkId::new("broad_phase", obstacles), |b| {
+            b.iter(|| schedule.run(&mut world))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, collision_lookups);
+criterion_main!(benches);
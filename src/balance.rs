@@ -0,0 +1,309 @@
+use std::{fmt::Write, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bot::{Controller, Observation},
+    enums::{BotKind, CrashCause},
+    headless::{run_batch, RunReport},
+    systems::autopilot::Autopilot,
+};
+
+// What to simulate. Loaded from RON, and overridden by the command line of `copter-sim`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SimConfig {
+    pub bot: BotKind,
+    pub first_seed: u64,
+    pub runs: u64,
+    // Seconds of game time after which a run counts as survived.
+    pub max_time: f32,
+    // Width in pixels of every bucket of the closest-gap histogram.
+    pub gap_bucket: f32,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            bot: BotKind::Autopilot,
+            first_seed: 0,
+            runs: 100,
+            max_time: 120.0,
+            gap_bucket: 10.0,
+        }
+    }
+}
+
+impl SimConfig {
+    // Unlike the settings, a config asked for by name must exist and parse.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&contents).map_err(|err| err.to_string())
+    }
+}
+
+pub fn controller(bot: BotKind) -> Box<dyn Controller> {
+    match bot {
+        BotKind::Autopilot => Box::new(Autopilot),
+        BotKind::Idle => Box::new(|_: &Observation| false),
+        BotKind::Hover => Box::new(|observation: &Observation| {
+            observation.copter_position.y + observation.copter_velocity * 0.1 < 0.0
+        }),
+    }
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct SurvivalStats {
+    pub mean: f32,
+    pub min: f32,
+    pub p10: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub max: f32,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct DeathCauses {
+    pub obstacle: u64,
+    pub top_border: u64,
+    pub bottom_border: u64,
+    // Still flying at the time limit.
+    pub survived: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GapBucket {
+    pub from: f32,
+    pub to: f32,
+    pub runs: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BalanceReport {
+    pub config: SimConfig,
+    pub survival_time: SurvivalStats,
+    pub death_causes: DeathCauses,
+    // Closest gap of every run to whatever it flew past.
+    pub closest_gap_histogram: Vec<GapBucket>,
+    // Fraction of seeds that spawned an obstacle the copter couldn't fit past.
+    pub impossible_seeds: f32,
+    pub runs: Vec<RunReport>,
+}
+
+// Value at a fraction of the way through sorted values.
+fn percentile(sorted: &[f32], fraction: f32) -> f32 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    sorted[((sorted.len() - 1) as f32 * fraction).round() as usize]
+}
+
+impl BalanceReport {
+    pub fn simulate(config: SimConfig) -> Result<Self, String> {
+        let runs = run_batch(
+            // Seeds near the end of the range wrap around to the start.
+            (0..config.runs).map(|i| config.first_seed.wrapping_add(i)),
+            || controller(config.bot),
+            config.max_time,
+        )?;
+        Ok(Self::new(config, runs))
+    }
+
+    pub fn new(config: SimConfig, runs: Vec<RunReport>) -> Self {
+        let mut survival: Vec<_> = runs.iter().map(|run| run.survival_time).collect();
+        survival.sort_by(f32::total_cmp);
+        let survival_time = SurvivalStats {
+            mean: survival.iter().sum::<f32>() / survival.len().max(1) as f32,
+            min: percentile(&survival, 0.0),
+            p10: percentile(&survival, 0.1),
+            p50: percentile(&survival, 0.5),
+            p90: percentile(&survival, 0.9),
+            max: percentile(&survival, 1.0),
+        };
+
+        let mut death_causes = DeathCauses::default();
+        for run in &runs {
+            match run.crash_cause {
+                Some(CrashCause::Obstacle) => death_causes.obstacle += 1,
+                Some(CrashCause::TopBorder) => death_causes.top_border += 1,
+                Some(CrashCause::BottomBorder) => death_causes.bottom_border += 1,
+                None => death_causes.survived += 1,
+            }
+        }
+
+        // Poking into a wall or an obstacle leaves a negative gap, which counts as zero.
+        let mut closest_gap_histogram: Vec<GapBucket> = Vec::new();
+        let bucket_width = config.gap_bucket.max(1.0);
+        for gap in runs.iter().filter_map(|run| run.closest_gap) {
+            let bucket = (gap.max(0.0) / bucket_width) as usize;
+            while closest_gap_histogram.len() <= bucket {
+                let from = closest_gap_histogram.len() as f32 * bucket_width;
+                closest_gap_histogram.push(GapBucket {
+                    from,
+                    to: from + bucket_width,
+                    runs: 0,
+                });
+            }
+            closest_gap_histogram[bucket].runs += 1;
+        }
+
+        let impossible = runs
+            .iter()
+            .filter(|run| run.impassable_obstacles > 0)
+            .count();
+
+        Self {
+            impossible_seeds: impossible as f32 / runs.len().max(1) as f32,
+            config,
+            survival_time,
+            death_causes,
+            closest_gap_histogram,
+            runs,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    // Long format, one `section,key,value` row per number, so it loads straight into a spreadsheet.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,key,value\n");
+        let mut row = |section: &str, key: &str, value: String| {
+            let _ = writeln!(csv, "{section},{key},{value}");
+        };
+
+        row("config", "bot", format!("{:?}", self.config.bot));
+        row("config", "first_seed", self.config.first_seed.to_string());
+        row("config", "runs", self.config.runs.to_string());
+        row("config", "max_time", self.config.max_time.to_string());
+
+        let survival = &self.survival_time;
+        for (key, value) in [
+            ("mean", survival.mean),
+            ("min", survival.min),
+            ("p10", survival.p10),
+            ("p50", survival.p50),
+            ("p90", survival.p90),
+            ("max", survival.max),
+        ] {
+            row("survival_time", key, value.to_string());
+        }
+
+        let causes = &self.death_causes;
+        for (key, value) in [
+            ("obstacle", causes.obstacle),
+            ("top_border", causes.top_border),
+            ("bottom_border", causes.bottom_border),
+            ("survived", causes.survived),
+        ] {
+            row("death_cause", key, value.to_string());
+        }
+
+        for bucket in &self.closest_gap_histogram {
+            row(
+                "closest_gap",
+                &format!("{}-{}", bucket.from, bucket.to),
+                bucket.runs.to_string(),
+            );
+        }
+
+        row(
+            "impossible_seeds",
+            "fraction",
+            self.impossible_seeds.to_string(),
+        );
+
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A run that crashed into an obstacle after some time, with its closest gap.
+    fn run(survival_time: f32, closest_gap: Option<f32>) -> RunReport {
+        RunReport {
+            seed: 0,
+            survival_time,
+            crash_cause: Some(CrashCause::Obstacle),
+            score: 0,
+            meters: 0.0,
+            obstacles_passed: 0,
+            closest_gap,
+            impassable_obstacles: 0,
+        }
+    }
+
+    fn histogram(report: &BalanceReport) -> Vec<(f32, f32, u64)> {
+        report
+            .closest_gap_histogram
+            .iter()
+            .map(|bucket| (bucket.from, bucket.to, bucket.runs))
+            .collect()
+    }
+
+    #[test]
+    fn empty_report_is_all_zero() {
+        let report = BalanceReport::new(SimConfig::default(), Vec::new());
+
+        assert_eq!(report.survival_time.mean, 0.0);
+        assert_eq!(report.survival_time.p50, 0.0);
+        assert_eq!(report.survival_time.max, 0.0);
+        assert!(report.closest_gap_histogram.is_empty());
+        assert_eq!(report.impossible_seeds, 0.0);
+    }
+
+    #[test]
+    fn single_run_is_every_percentile() {
+        let report = BalanceReport::new(SimConfig::default(), vec![run(12.5, Some(3.0))]);
+        let survival = &report.survival_time;
+
+        for value in [
+            survival.mean,
+            survival.min,
+            survival.p10,
+            survival.p50,
+            survival.p90,
+            survival.max,
+        ] {
+            assert_eq!(value, 12.5);
+        }
+        assert_eq!(histogram(&report), vec![(0.0, 10.0, 1)]);
+    }
+
+    #[test]
+    fn percentiles_round_to_the_closest_run() {
+        let sorted: Vec<_> = (0..11).map(|i| i as f32).collect();
+
+        assert_eq!(percentile(&sorted, 0.0), 0.0);
+        assert_eq!(percentile(&sorted, 0.1), 1.0);
+        assert_eq!(percentile(&sorted, 0.5), 5.0);
+        assert_eq!(percentile(&sorted, 1.0), 10.0);
+    }
+
+    // Buckets include where they start and exclude where they end, negative gaps go in the first.
+    #[test]
+    fn gaps_on_a_boundary_go_in_the_next_bucket() {
+        let runs = vec![
+            run(1.0, Some(-5.0)),
+            run(1.0, Some(0.0)),
+            run(1.0, Some(9.99)),
+            run(1.0, Some(10.0)),
+            run(1.0, Some(30.0)),
+            run(1.0, None),
+        ];
+        let report = BalanceReport::new(SimConfig::default(), runs);
+
+        assert_eq!(
+            histogram(&report),
+            vec![
+                (0.0, 10.0, 3),
+                (10.0, 20.0, 1),
+                (20.0, 30.0, 0),
+                (30.0, 40.0, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use std::{env, fs, path::PathBuf, process};
+
+use copter::{
+    balance::{BalanceReport, SimConfig},
+    enums::BotKind,
+};
+
+const USAGE: &str = "\
+Flies headless runs with a bot and reports how they went.
+
+Usage: copter-sim [options]
+
+Options:
+  --config <file>     RON file with a SimConfig, overridden by the options below
+  --bot <name>        autopilot, idle or hover
+  --seeds <a>..<b>    Seeds to fly, end exclusive
+  --max-time <secs>   Game time after which a run counts as survived
+  --gap-bucket <px>   Width of the closest-gap histogram buckets
+  --format <format>   csv or json (default: csv)
+  --output <file>     Write the report to a file instead of stdout
+  --help              Show this message";
+
+enum Format {
+    Csv,
+    Json,
+}
+
+fn parse_bot(name: &str) -> Result<BotKind, String> {
+    match name {
+        "autopilot" => Ok(BotKind::Autopilot),
+        "idle" => Ok(BotKind::Idle),
+        "hover" => Ok(BotKind::Hover),
+        _ => Err(format!("unknown bot `{name}`")),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for {option}"))
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    // The config is read first wherever it appears, so the other options always win.
+    let mut config = match args.iter().position(|arg| arg == "--config") {
+        Some(index) => {
+            let path = args.get(index + 1).ok_or("missing value for --config")?;
+            SimConfig::load(&PathBuf::from(path))
+                .map_err(|err| format!("could not load {path}: {err}"))?
+        }
+        None => SimConfig::default(),
+    };
+    let mut format = Format::Csv;
+    let mut output = None;
+
+    let mut args = args.into_iter();
+    while let Some(option) = args.next() {
+        if option == "--help" || option == "-h" {
+            println!("{USAGE}");
+            return Ok(());
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {option}"))?;
+
+        match option.as_str() {
+            "--config" => {}
+            "--bot" => config.bot = parse_bot(&value)?,
+            "--seeds" => {
+                let (start, end) = value
+                    .split_once("..")
+                    .ok_or_else(|| format!("expected a range like 0..100, got `{value}`"))?;
+                let start: u64 = parse_number(&option, start)?;
+                let end: u64 = parse_number(&option, end)?;
+                config.first_seed = start;
+                config.runs = end.saturating_sub(start);
+            }
+            "--max-time" => config.max_time = parse_number(&option, &value)?,
+            "--gap-bucket" => config.gap_bucket = parse_number(&option, &value)?,
+            "--format" => {
+                format = match value.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    _ => return Err(format!("unknown format `{value}`")),
+                }
+            }
+            "--output" => output = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown option {option}")),
+        }
+    }
+
+    let report = BalanceReport::simulate(config)?;
+    let contents = match format {
+        Format::Csv => report.to_csv(),
+        Format::Json => report.to_json(),
+    };
+
+    match output {
+        Some(path) => fs::write(&path, contents)
+            .map_err(|err| format!("could not write {}: {err}", path.display())),
+        None => {
+            print!("{contents}");
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("copter-sim: {err}\n\n{USAGE}");
+        process::exit(2);
+    }
+}
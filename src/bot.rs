@@ -0,0 +1,140 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Copter, Obstacle},
+    constants::{COPTER_SIZE, OBSERVED_OBSTACLES},
+    enums::{Action, Player},
+    resources::{ActionState, BorderTileCurrentHeight, Playfield},
+    terrain::Terrain,
+};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ObstacleObservation {
+    pub position: Vec2,
+    pub size: Vec2,
+}
+
+// A sample of the height-field of the top or the bottom wall.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WallObservation {
+    pub x: f32,
+    pub height: f32,
+    // Hangs from the top of the playfield rather than standing on the bottom.
+    pub top: bool,
+}
+
+// Everything a bot gets to see before deciding on lift.
+#[derive(Clone, Debug, Serialize)]
+pub struct Observation {
+    pub copter_position: Vec2,
+    pub copter_velocity: f32,
+    // The next few obstacles that are not yet behind the copter, nearest first.
+    pub obstacles: Vec<ObstacleObservation>,
+    // Height of the border currently being generated on the right.
+    pub top_border: f32,
+    pub bottom_border: f32,
+    // Samples of the walls on screen, which run straight from one to the next.
+    pub walls: Vec<WallObservation>,
+    pub playfield_size: Vec2,
+}
+
+// A pilot for the copter, be it a heuristic or a learned policy.
+pub trait Controller: Send + Sync {
+    // Whether to hold lift until the next tick.
+    fn decide(&mut self, observation: &Observation) -> bool;
+}
+
+impl<F: FnMut(&Observation) -> bool + Send + Sync> Controller for F {
+    fn decide(&mut self, observation: &Observation) -> bool {
+        self(observation)
+    }
+}
+
+// Flies the copter instead of the gamer while set.
+#[derive(Resource, Default)]
+pub struct BotPilot(pub Option<Box<dyn Controller>>);
+
+#[derive(SystemParam)]
+pub struct Observer<'w, 's> {
+    playfield: Res<'w, Playfield>,
+    border_height: Res<'w, BorderTileCurrentHeight>,
+    copter_query: Query<'w, 's, (&'static Copter, &'static Transform)>,
+    obstacle_query: Query<'w, 's, (&'static Transform, &'static Sprite), With<Obstacle>>,
+    terrain: Res<'w, Terrain>,
+}
+
+// Both walls of the cave, two observations for every sample of its height-field.
+pub fn observe_walls(terrain: &Terrain) -> Vec<WallObservation> {
+    terrain
+        .samples()
+        .flat_map(|sample| {
+            [
+                WallObservation {
+                    x: sample.x,
+                    height: sample.top,
+                    top: true,
+                },
+                WallObservation {
+                    x: sample.x,
+                    height: sample.bottom,
+                    top: false,
+                },
+            ]
+        })
+        .collect()
+}
+
+impl Observer<'_, '_> {
+    pub fn observe(&self) -> Option<Observation> {
+        // Bots only ever fly player one's copter.
+        let (copter, copter_transform) = self
+            .copter_query
+            .iter()
+            .find(|(copter, _)| copter.player == Player::ONE)?;
+        let copter_position = copter_transform.translation.truncate();
+        let copter_left = copter_position.x - COPTER_SIZE.x * 0.5;
+
+        let mut obstacles: Vec<_> = self
+            .obstacle_query
+            .iter()
+            .map(|(transform, sprite)| ObstacleObservation {
+                position: transform.translation.truncate(),
+                size: sprite.custom_size.unwrap_or(Vec2::ONE),
+            })
+            .filter(|obstacle| obstacle.position.x + obstacle.size.x * 0.5 > copter_left)
+            .collect();
+        obstacles.sort_by(|a, b| a.position.x.total_cmp(&b.position.x));
+        obstacles.truncate(OBSERVED_OBSTACLES);
+
+        Some(Observation {
+            copter_position,
+            copter_velocity: copter.velocity,
+            obstacles,
+            top_border: self.border_height.top_border,
+            bottom_border: self.border_height.bottom_border,
+            walls: observe_walls(&self.terrain),
+            playfield_size: Vec2::new(self.playfield.width, self.playfield.height),
+        })
+    }
+}
+
+// Replaces the gamer's lift with the bot's decision.
+pub fn fly_bot(
+    mut bot_pilot: ResMut<BotPilot>,
+    mut action_state: ResMut<ActionState>,
+    observer: Observer,
+) {
+    let Some(controller) = bot_pilot.0.as_mut() else {
+        return;
+    };
+    let Some(observation) = observer.observe() else {
+        return;
+    };
+
+    if controller.decide(&observation) {
+        action_state.press(Action::Lift);
+    } else {
+        action_state.release(Action::Lift);
+    }
+}
@@ -0,0 +1,331 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use crate::{
+    bot::ObstacleObservation,
+    components::{Copter, Obstacle, Score},
+    constants::{BROADCAST_INTERVAL, BROADCAST_TIMEOUT},
+    enums::{CrashCause, Player},
+    pool::ColliderPool,
+    resources::{GameRng, GameState, Playfield, ScoreWeights},
+    settings::Settings,
+    simulation::copter_bundle,
+    terrain::Terrain,
+};
+
+/*
+    Broadcast:
+        A read-only stream of the world, sent as JSON text messages over a
+        WebSocket every BROADCAST_INTERVAL seconds. Another instance started
+        with `--spectate` mirrors it, and browsers opening the same address
+        over plain HTTP get a page that draws it. Spectators never send
+        anything that reaches the game.
+*/
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CopterSnapshot {
+    pub player: usize,
+    pub position: Vec2,
+    pub velocity: f32,
+    pub crash_cause: Option<CrashCause>,
+    pub score: Score,
+    // Weighted total of the score, as shown to the gamer.
+    pub total: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    pub seed: u64,
+    pub game_over: bool,
+    pub playfield_size: Vec2,
+    pub copters: Vec<CopterSnapshot>,
+    pub obstacles: Vec<ObstacleObservation>,
+    // The height-field of the walls, drawn as is by every spectator.
+    pub terrain: Terrain,
+}
+
+const SPECTATOR_PAGE: &str = include_str!("spectator.html");
+
+// Broadcasts of this instance, sent to every spectator by a thread of their own.
+#[derive(Resource)]
+pub struct Broadcast {
+    // Snapshots are dropped rather than queued when spectators fall behind.
+    snapshots: SyncSender<String>,
+    timer: Timer,
+}
+
+// Peeks at the request until its headers are complete, as it may arrive in pieces.
+fn peek_request(stream: &TcpStream, request: &mut [u8]) -> io::Result<usize> {
+    let deadline = Instant::now() + Duration::from_secs_f32(BROADCAST_TIMEOUT);
+    let mut peeked = 0;
+    loop {
+        // Blocks until the first bytes arrive, or the read timeout passes.
+        let read = stream.peek(request)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        if read == request.len() || request[..read].windows(4).any(|end| end == b"\r\n\r\n") {
+            return Ok(read);
+        }
+        if Instant::now() >= deadline {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        // Peeking returns right away while part of the request is there.
+        if read == peeked {
+            thread::sleep(Duration::from_millis(10));
+        }
+        peeked = read;
+    }
+}
+
+// Plain HTTP requests get the page, WebSocket upgrades get the stream.
+fn welcome(stream: TcpStream, spectators: &Mutex<Vec<SyncSender<String>>>) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    // Spectators that never finish their request, or stop reading the stream, are given up on.
+    stream.set_read_timeout(Some(Duration::from_secs_f32(BROADCAST_TIMEOUT)))?;
+    stream.set_write_timeout(Some(Duration::from_secs_f32(BROADCAST_TIMEOUT)))?;
+    let mut request = [0; 2048];
+    let read = peek_request(&stream, &mut request)?;
+    let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+
+    if !request.contains("upgrade: websocket") {
+        // Unread requests make closing the connection reset it, losing the page.
+        let mut stream = stream;
+        stream.read_exact(&mut vec![0; read])?;
+        return write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{SPECTATOR_PAGE}",
+            SPECTATOR_PAGE.len()
+        );
+    }
+
+    let socket =
+        tungstenite::accept(stream).map_err(|error| io::Error::other(error.to_string()))?;
+    let (sender, snapshots) = mpsc::sync_channel(1);
+    if let Ok(mut spectators) = spectators.lock() {
+        spectators.push(sender);
+    }
+    stream_snapshots(socket, snapshots);
+    Ok(())
+}
+
+// Until the spectator hangs up, or falls behind by more than the write timeout.
+fn stream_snapshots(mut socket: WebSocket<TcpStream>, snapshots: Receiver<String>) {
+    for snapshot in snapshots {
+        if socket.send(Message::text(snapshot)).is_err() {
+            return;
+        }
+    }
+}
+
+// Hands every snapshot to the spectators, without waiting on any of them.
+fn send_snapshots(snapshots: Receiver<String>, spectators: Arc<Mutex<Vec<SyncSender<String>>>>) {
+    for snapshot in snapshots {
+        let Ok(mut spectators) = spectators.lock() else {
+            return;
+        };
+        // Spectators still sending the last snapshot skip this one, the ones that left are forgotten.
+        spectators.retain(|spectator| {
+            !matches!(
+                spectator.try_send(snapshot.clone()),
+                Err(TrySendError::Disconnected(_))
+            )
+        });
+    }
+}
+
+pub fn broadcast(address: &str) -> io::Result<Broadcast> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!(
+        "Broadcasting on ws://{0}, or watch at http://{0}",
+        listener.local_addr()?
+    );
+
+    let spectators = Arc::new(Mutex::new(Vec::new()));
+    let welcomed = spectators.clone();
+    // Every spectator is welcomed on a thread of its own, so that a slow one holds up no other.
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let welcomed = welcomed.clone();
+            thread::spawn(move || {
+                if let Err(error) = welcome(stream, &welcomed) {
+                    eprintln!("Could not welcome a spectator: {error}");
+                }
+            });
+        }
+    });
+
+    let (snapshots, receiver) = mpsc::sync_channel(1);
+    thread::spawn(move || send_snapshots(receiver, spectators));
+
+    Ok(Broadcast {
+        snapshots,
+        timer: Timer::from_seconds(BROADCAST_INTERVAL, TimerMode::Repeating),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn broadcast_world(
+    time: Res<Time>,
+    mut broadcast: ResMut<Broadcast>,
+    game_state: Res<GameState>,
+    rng: Res<GameRng>,
+    playfield: Res<Playfield>,
+    score_weights: Res<ScoreWeights>,
+    copter_query: Query<(&Copter, &Transform, &Score)>,
+    obstacle_query: Query<(&Transform, &Sprite), With<Obstacle>>,
+    terrain: Res<Terrain>,
+) {
+    if !broadcast.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let snapshot = WorldSnapshot {
+        seed: rng.seed,
+        game_over: game_state.game_over,
+        playfield_size: Vec2::new(playfield.width, playfield.height),
+        copters: copter_query
+            .iter()
+            .map(|(copter, transform, score)| CopterSnapshot {
+                player: copter.player.0,
+                position: transform.translation.truncate(),
+                velocity: copter.velocity,
+                crash_cause: copter.crash_cause,
+                score: score.clone(),
+                total: score.total(&score_weights),
+            })
+            .collect(),
+        obstacles: obstacle_query
+            .iter()
+            .map(|(transform, sprite)| ObstacleObservation {
+                position: transform.translation.truncate(),
+                size: sprite.custom_size.unwrap_or(Vec2::ONE),
+            })
+            .collect(),
+        terrain: terrain.clone(),
+    };
+
+    if let Ok(snapshot) = serde_json::to_string(&snapshot) {
+        let _ = broadcast.snapshots.try_send(snapshot);
+    }
+}
+
+// Latest snapshot of the broadcast being watched.
+#[derive(Resource)]
+pub struct Spectator {
+    latest: Arc<Mutex<Option<WorldSnapshot>>>,
+}
+
+pub fn spectate(address: &str) -> io::Result<Spectator> {
+    let url = if address.starts_with("ws://") {
+        address.to_string()
+    } else {
+        format!("ws://{address}")
+    };
+    let (mut socket, _) =
+        tungstenite::connect(&url).map_err(|error| io::Error::other(error.to_string()))?;
+    eprintln!("Watching the broadcast on {url}");
+
+    let latest = Arc::new(Mutex::new(None));
+    let received = latest.clone();
+    thread::spawn(move || {
+        loop {
+            match socket.read() {
+                Ok(Message::Text(text)) => {
+                    let Ok(snapshot) = serde_json::from_str(text.as_str()) else {
+                        continue;
+                    };
+                    if let Ok(mut latest) = received.lock() {
+                        *latest = Some(snapshot);
+                    }
+                }
+                Ok(_) => {}
+                Err(_) => break,
+            }
+        }
+        eprintln!("The broadcast ended");
+    });
+
+    Ok(Spectator { latest })
+}
+
+// Rebuilds the world from the latest snapshot. Nothing is simulated while spectating.
+#[allow(clippy::too_many_arguments)]
+pub fn mirror_broadcast(
+    mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
+    spectator: Res<Spectator>,
+    settings: Res<Settings>,
+    mut game_state: ResMut<GameState>,
+    mut copter_query: Query<(Entity, &mut Copter, &mut Transform, &mut Score), Without<Obstacle>>,
+    mut terrain: ResMut<Terrain>,
+    mut obstacle_query: Query<(Entity, &mut Transform, &mut Sprite), With<Obstacle>>,
+) {
+    let Some(snapshot) = spectator
+        .latest
+        .lock()
+        .ok()
+        .and_then(|mut latest| latest.take())
+    else {
+        return;
+    };
+    game_state.game_over = snapshot.game_over;
+
+    if copter_query.iter().count() != snapshot.copters.len() {
+        for (entity, ..) in copter_query.iter() {
+            commands.entity(entity).despawn();
+        }
+        for copter in &snapshot.copters {
+            commands.spawn(copter_bundle(
+                Player(copter.player),
+                snapshot.copters.len(),
+                &settings,
+            ));
+        }
+        // Positioned by the next snapshot, once the copters exist.
+        return;
+    }
+
+    for (_, mut copter, mut transform, mut score) in copter_query.iter_mut() {
+        let Some(mirrored) = snapshot
+            .copters
+            .iter()
+            .find(|mirrored| mirrored.player == copter.player.0)
+        else {
+            continue;
+        };
+        copter.velocity = mirrored.velocity;
+        copter.crash_cause = mirrored.crash_cause;
+        transform.translation = mirrored.position.extend(transform.translation.z);
+        *score = mirrored.score.clone();
+    }
+
+    pool.lay_out(
+        &mut commands,
+        &mut obstacle_query,
+        snapshot
+            .obstacles
+            .iter()
+            .map(|obstacle| (obstacle.position.extend(1.0), obstacle.size)),
+        settings.display.color_theme.wall(),
+    );
+
+    *terrain = snapshot.terrain;
+}
+
+// Leaving to the main menu stops watching, and the world goes back to the attract mode.
+pub fn stop_spectating(mut commands: Commands) {
+    commands.remove_resource::<Spectator>();
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::components::Obstacle;
+
+/*
+    Broad-phase:
+        Every obstacle has a proxy holding where it starts and ends on the
+        x-axis, and the proxies are kept sorted by where they start. Finding
+        what might overlap a range of x is then a binary search for the last
+        proxy starting before the range ends, walking back until the proxies
+        start so far left that even the widest one can't reach into it.
+        Only those go on to the exact check.
+
+        The proxies are kept from one tick to the next and follow their
+        obstacles as they move, while the proxies of obstacles that are gone
+        are dropped. New obstacles come in on the right and everything
+        scrolls at about the same speed, so the proxies stay close to sorted
+        and an insertion sort puts them back in order in about one pass.
+*/
+
+#[derive(Clone, Copy)]
+struct Proxy {
+    entity: Entity,
+    min_x: f32,
+    max_x: f32,
+    // Whether the collider was still there at the latest update.
+    seen: bool,
+}
+
+#[derive(Resource, Default)]
+pub struct BroadPhase {
+    proxies: Vec<Proxy>,
+    widest: f32,
+}
+
+impl BroadPhase {
+    // Moves the proxies to the bounds of every collider on the x-axis, as colliders come and go.
+    pub fn update(&mut self, colliders: impl IntoIterator<Item = (Entity, f32, f32)>) {
+        let indices: HashMap<Entity, usize> = self
+            .proxies
+            .iter()
+            .enumerate()
+            .map(|(index, proxy)| (proxy.entity, index))
+            .collect();
+        for proxy in self.proxies.iter_mut() {
+            proxy.seen = false;
+        }
+
+        let mut added = Vec::new();
+        for (entity, min_x, max_x) in colliders {
+            let proxy = Proxy {
+                entity,
+                min_x,
+                max_x,
+                seen: true,
+            };
+            match indices.get(&entity) {
+                Some(&index) => self.proxies[index] = proxy,
+                None => added.push(proxy),
+            }
+        }
+        self.proxies.retain(|proxy| proxy.seen);
+        self.proxies.extend(added);
+
+        for i in 1..self.proxies.len() {
+            let mut j = i;
+            while j > 0 && self.proxies[j - 1].min_x > self.proxies[j].min_x {
+                self.proxies.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        self.widest = self
+            .proxies
+            .iter()
+            .map(|proxy| proxy.max_x - proxy.min_x)
+            .fold(0.0, f32::max);
+    }
+
+    // Entities that may overlap the range on the x-axis, ends included.
+    pub fn candidates(&self, min_x: f32, max_x: f32) -> impl Iterator<Item = Entity> + '_ {
+        let end = self.proxies.partition_point(|proxy| proxy.min_x <= max_x);
+        let start = self.proxies[..end].partition_point(|proxy| proxy.min_x < min_x - self.widest);
+        self.proxies[start..end]
+            .iter()
+            .filter(move |proxy| proxy.max_x >= min_x)
+            .map(|proxy| proxy.entity)
+    }
+
+    pub fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty()
+    }
+}
+
+// Runs right before the collision detection, once everything moved and spawned this tick.
+pub fn update_broad_phase(
+    mut broad_phase: ResMut<BroadPhase>,
+    obstacle_query: Query<(Entity, &Transform, &Sprite), With<Obstacle>>,
+) {
+    broad_phase.update(obstacle_query.iter().map(|(entity, transform, sprite)| {
+        let half_width = sprite.custom_size.unwrap_or(Vec2::ONE).x * 0.5;
+        (
+            entity,
+            transform.translation.x - half_width,
+            transform.translation.x + half_width,
+        )
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Proxies at the bounds given for each entity, in whatever order.
+    fn broad_phase(bounds: &[(f32, f32)]) -> BroadPhase {
+        let mut broad_phase = BroadPhase::default();
+        broad_phase.update(
+            bounds
+                .iter()
+                .enumerate()
+                .map(|(i, &(min_x, max_x))| (Entity::from_raw(i as u32), min_x, max_x)),
+        );
+        broad_phase
+    }
+
+    fn candidates(broad_phase: &BroadPhase, min_x: f32, max_x: f32) -> Vec<u32> {
+        let mut indices: Vec<_> = broad_phase
+            .candidates(min_x, max_x)
+            .map(|entity| entity.index())
+            .collect();
+        indices.sort();
+        indices
+    }
+
+    #[test]
+    fn only_proxies_overlapping_the_range_are_candidates() {
+        let broad_phase = broad_phase(&[(300.0, 400.0), (-50.0, 50.0), (100.0, 200.0)]);
+
+        assert_eq!(candidates(&broad_phase, 0.0, 150.0), vec![1, 2]);
+        assert_eq!(candidates(&broad_phase, 210.0, 290.0), Vec::<u32>::new());
+    }
+
+    // The range is inclusive, the exact check decides about obstacles merely touching the copter.
+    #[test]
+    fn proxies_touching_the_range_are_candidates() {
+        let broad_phase = broad_phase(&[(-100.0, 0.0), (50.0, 60.0)]);
+
+        assert_eq!(candidates(&broad_phase, 0.0, 50.0), vec![0, 1]);
+    }
+
+    // A wide proxy starting far to the left still reaches into the range.
+    #[test]
+    fn wide_proxies_are_found_from_their_start() {
+        let broad_phase = broad_phase(&[(-1000.0, 1000.0), (-900.0, -800.0), (-500.0, -400.0)]);
+
+        assert_eq!(candidates(&broad_phase, 0.0, 10.0), vec![0]);
+    }
+
+    // Obstacles that are gone by the next update are no candidates anymore.
+    #[test]
+    fn updating_replaces_every_proxy() {
+        let mut broad_phase = broad_phase(&[(0.0, 10.0), (20.0, 30.0)]);
+        broad_phase.update([(Entity::from_raw(1), 15.0, 25.0)]);
+
+        assert_eq!(broad_phase.len(), 1);
+        assert_eq!(candidates(&broad_phase, 0.0, 100.0), vec![1]);
+    }
+
+    // Proxies moving past one another are sorted back into place.
+    #[test]
+    fn proxies_follow_their_colliders() {
+        let mut broad_phase = broad_phase(&[(0.0, 10.0), (20.0, 30.0), (40.0, 50.0)]);
+        broad_phase.update([
+            (Entity::from_raw(0), 60.0, 70.0),
+            (Entity::from_raw(1), 20.0, 30.0),
+            (Entity::from_raw(2), -20.0, -10.0),
+        ]);
+
+        assert_eq!(candidates(&broad_phase, -15.0, 25.0), vec![1, 2]);
+        assert_eq!(candidates(&broad_phase, 55.0, 65.0), vec![0]);
+        assert_eq!(candidates(&broad_phase, 0.0, 10.0), Vec::<u32>::new());
+    }
+}
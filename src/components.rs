@@ -1,17 +1,221 @@
-use bevy::ecs::component::Component;
+use std::time::Duration;
 
-#[derive(Component)]
+use bevy::{
+    ecs::{component::Component, entity::Entity},
+    time::{Timer, TimerMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{
+        NEAR_MISS_BORDER_COOLDOWN, NEAR_MISS_COMBO_TIME, NEAR_MISS_MAX_MULTIPLIER, PIXELS_PER_METER,
+    },
+    enums::{Action, CrashCause, GameMode, Music, Player},
+    resources::ScoreWeights,
+};
+
+#[derive(Component, Clone, Serialize, Deserialize)]
 pub struct Copter {
     pub velocity: f32,
+    pub player: Player,
+    // Whether lift is held for the current tick, by whoever flies the copter.
+    pub lift: bool,
+    // Crashed copters stay where they crashed, the run goes on while any copter still flies.
+    pub crash_cause: Option<CrashCause>,
+}
+
+impl Copter {
+    pub fn crashed(&self) -> bool {
+        self.crash_cause.is_some()
+    }
+}
+
+// Progress of a copter through the run, tracked per source to break it down on game over.
+#[derive(Component, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Score {
+    // Pixels scrolled since the run started.
+    pub distance: f32,
+    pub obstacles_passed: u32,
+    pub near_miss_points: u32,
+}
+
+impl Score {
+    pub fn meters(&self) -> f32 {
+        self.distance / PIXELS_PER_METER
+    }
+
+    pub fn total(&self, weights: &ScoreWeights) -> u32 {
+        (self.meters() * weights.meter
+            + self.obstacles_passed as f32 * weights.obstacle
+            + self.near_miss_points as f32 * weights.near_miss_point) as u32
+    }
+}
+
+// Consecutive near-misses within the combo window raise the multiplier.
+#[derive(Component, Clone, Serialize, Deserialize)]
+pub struct NearMissCombo {
+    pub multiplier: u32,
+    pub combo_timer: Timer,
+    pub border_cooldown: Timer,
+}
+
+impl Default for NearMissCombo {
+    fn default() -> Self {
+        let mut combo_timer = Timer::new(
+            Duration::from_secs_f32(NEAR_MISS_COMBO_TIME),
+            TimerMode::Once,
+        );
+        combo_timer.tick(Duration::from_secs_f32(NEAR_MISS_COMBO_TIME));
+        let mut border_cooldown = Timer::new(
+            Duration::from_secs_f32(NEAR_MISS_BORDER_COOLDOWN),
+            TimerMode::Once,
+        );
+        border_cooldown.tick(Duration::from_secs_f32(NEAR_MISS_BORDER_COOLDOWN));
+
+        Self {
+            multiplier: 0,
+            combo_timer,
+            border_cooldown,
+        }
+    }
+}
+
+impl NearMissCombo {
+    // Registers a near-miss and returns the multiplier it was awarded with.
+    pub fn register(&mut self) -> u32 {
+        self.multiplier = if self.combo_timer.finished() {
+            1
+        } else {
+            (self.multiplier + 1).min(NEAR_MISS_MAX_MULTIPLIER)
+        };
+        self.combo_timer.reset();
+        self.multiplier
+    }
 }
 
 #[derive(Component)]
 pub struct Obstacle;
 
+// Replays the best run on the current course.
+#[derive(Component)]
+pub struct Ghost;
+
+// Checkpoint or finish line of a level, at its distance along the course.
+#[derive(Component)]
+pub struct CourseMarker {
+    pub distance: f32,
+}
+
 #[derive(Component)]
 pub struct ScoreText;
 
+// Status and help of the level editor.
+#[derive(Component)]
+pub struct EditorText;
+
+#[derive(Component)]
+pub struct ScoreBreakdownText;
+
+// Mesh of the top or the bottom wall of the cave.
+#[derive(Component)]
+pub struct Wall {
+    pub top: bool,
+}
+
+#[derive(Component)]
+pub struct MainCamera;
+
+#[derive(Component)]
+pub struct Background;
+
+// Bar covering the area outside of the playfield when letterboxing.
+#[derive(Component)]
+pub struct Letterbox;
+
+// Obstacle that came within the near-miss margin of these copters.
+#[derive(Component)]
+pub struct NearMissCandidate(pub Vec<Entity>);
+
+// Obstacle that is behind the copter and has been scored.
+#[derive(Component)]
+pub struct Passed;
+
+// Obstacle entity waiting in the collider pool to be spawned again.
+#[derive(Component)]
+pub struct Parked;
+
+#[derive(Component)]
+pub struct FloatingLabel {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct RotorSound;
+
+// One of the looping music tracks, faded in or out depending on what is being played.
+#[derive(Component)]
+pub struct MusicTrack {
+    pub music: Music,
+    pub fade: f32,
+}
+
+#[derive(Component)]
+pub struct FpsText;
+
+// Help on the controls, following the bindings.
+#[derive(Component)]
+pub struct InstructionsText;
+
+// Selectable entry of a menu screen, in the order it is navigated.
+#[derive(Component)]
+pub struct MenuItem(pub usize);
+
+#[derive(Component)]
+pub enum MainMenuItem {
+    Play,
+    GameModes,
+    HighScores,
+    Settings,
+    Quit,
+}
+
+#[derive(Component)]
+pub enum GameModeItem {
+    Mode(GameMode),
+    Back,
+}
+
+#[derive(Component)]
+pub struct HighScoresBack;
+
+#[derive(Component)]
+pub enum PauseItem {
+    Resume,
+    Settings,
+    MainMenu,
+}
+
+#[derive(Component)]
+pub enum SettingsItem {
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    Fullscreen,
+    Vsync,
+    PlayfieldScaling,
+    ReducedMotion,
+    ScreenShake,
+    ZoomPulse,
+    CameraFollow,
+    FollowFactor,
+    ColorTheme,
+    ShowFps,
+    InputBindings,
+    Back,
+}
+
 #[derive(Component)]
-pub struct BorderTile {
-    pub height: f32,
+pub enum BindingItem {
+    Action(Action),
+    Back,
 }
@@ -4,11 +4,18 @@ use bevy::math::Vec2;
 pub const WINDOW_WIDTH: f32 = 1024.0;
 pub const WINDOW_HEIGHT: f32 = 720.0;
 
+// Playfield Settings. The camera scales the playfield to fit whatever size the window has.
+pub const PLAYFIELD_WIDTH: f32 = 1024.0;
+pub const PLAYFIELD_HEIGHT: f32 = 720.0;
+
 // Copter Settings
 pub const COPTER_SIZE: Vec2 = Vec2::new(50.0, 50.0);
 pub const LIFT: f32 = 2250.0;
 pub const GRAVITY: f32 = 1000.0;
 pub const NEGATE_DELAY: f32 = 2.0;
+pub const COPTER_START_X: f32 = -300.0;
+// Distance from the middle of the playfield the outermost copters of a race start at.
+pub const VERSUS_START_SPREAD: f32 = 60.0;
 
 // Obstacle Settings
 pub const OBSTACLE_SPAWN_TIME: f32 = 1.25;
@@ -16,13 +23,88 @@ pub const OBSTACLE_HEIGHT: f32 = 250.0;
 pub const OBSTACLE_WIDTH: f32 = 100.0;
 pub const OBSTACLE_SPEED: f32 = 600.0;
 
+// Score Settings
+pub const PIXELS_PER_METER: f32 = 60.0;
+pub const SCORE_PER_METER: f32 = 0.1;
+pub const SCORE_PER_OBSTACLE: f32 = 10.0;
+pub const SCORE_PER_NEAR_MISS_POINT: f32 = 1.0;
+
+// Near-Miss Settings
+pub const NEAR_MISS_MARGIN: f32 = 25.0; // Max gap in pixels between the copter and what it passed.
+pub const NEAR_MISS_POINTS: u32 = 5;
+pub const NEAR_MISS_COMBO_TIME: f32 = 2.0; // Seconds to chain another near-miss into the combo.
+pub const NEAR_MISS_MAX_MULTIPLIER: u32 = 5;
+pub const NEAR_MISS_BORDER_COOLDOWN: f32 = 1.0; // Hugging a wall only counts once per cooldown.
+pub const FLOATING_LABEL_TIME: f32 = 0.8;
+pub const FLOATING_LABEL_SPEED: f32 = 80.0;
+
 // BorderTile Obstacle Settings
 pub const DEFAULT_BORDERTILE_HEIGHT: f32 = 75.0;
 pub const MIN_BORDERTILE_HEIGHT: f32 = 50.0;
 pub const MAX_BORDERTILE_HEIGHT: f32 = 125.0;
-pub const BORDERTILE_WIDTH: f32 = 64.0; // Perfect multiple of WINDOW_WIDTH = 1024.0
+pub const BORDERTILE_WIDTH: f32 = 64.0; // Perfect multiple of PLAYFIELD_WIDTH = 1024.0
 pub const BORDERTILE_SPEED: f32 = OBSTACLE_SPEED; // IDK just to match
 pub const BORDERTILE_SPAWN_TIME: f32 = 0.3;
 
 // BorderTile Fluctuation Settings
 pub const FLUCTUATION_PER_FRAME: f32 = 5.0;
+
+// Attract Mode Settings
+pub const ATTRACT_IDLE_TIME: f32 = 10.0; // Seconds idle on game over before the demo takes over.
+pub const ATTRACT_RESTART_TIME: f32 = 1.5; // Seconds the demo lingers on a crash.
+pub const AUTOPILOT_LOOKAHEAD: f32 = 0.15; // Seconds ahead the autopilot predicts the copter's y.
+pub const AUTOPILOT_SAFETY_MARGIN: f32 = 10.0;
+
+// Bot Settings
+pub const OBSERVED_OBSTACLES: usize = 3; // Obstacles ahead of the copter a bot gets to see.
+pub const SIMULATION_TIMESTEP: f32 = 1.0 / 60.0; // Seconds per tick of the simulation.
+
+// Gym Settings
+pub const GYM_DEFAULT_ADDRESS: &str = "127.0.0.1:5555";
+pub const GYM_CRASH_REWARD: f32 = -100.0;
+
+// LAN Settings
+pub const LAN_DEFAULT_ADDRESS: &str = "0.0.0.0:5556";
+pub const LAN_MAX_PLAYERS: usize = 8;
+pub const LAN_INPUT_DELAY: u64 = 4; // Ticks between pressing lift and the copter lifting.
+
+// Level Settings
+pub const COURSE_MARKER_WIDTH: f32 = 8.0;
+pub const COURSE_MARKER_ALPHA: f32 = 0.5;
+
+// Editor Settings
+pub const EDITOR_NEW_LEVEL_LENGTH: f32 = 6000.0;
+pub const EDITOR_HANDLE_RADIUS: f32 = 12.0;
+pub const EDITOR_SCROLL_SPEED: f32 = 1200.0; // Pixels per second while an arrow key is held.
+pub const EDITOR_WHEEL_STEP: f32 = 120.0;
+pub const EDITOR_MIN_OBSTACLE_SIZE: f32 = 20.0;
+
+// Practice Settings
+pub const PRACTICE_CHECKPOINT_TIME: f32 = 3.0; // Seconds of flight between snapshots of the world.
+pub const PRACTICE_RESPAWN_TIME: f32 = 1.0; // Seconds a crash is shown before flying on from the checkpoint.
+pub const PRACTICE_MIN_FLIGHT: f32 = 1.0; // Checkpoints crashed out of sooner are given up on.
+
+// Broadcast Settings
+pub const BROADCAST_DEFAULT_ADDRESS: &str = "127.0.0.1:5557";
+pub const BROADCAST_INTERVAL: f32 = 1.0 / 60.0; // Seconds between snapshots sent to spectators.
+pub const BROADCAST_TIMEOUT: f32 = 5.0; // Seconds a spectator may take to send its request.
+
+// Ghost Settings
+pub const GHOST_SAMPLE_TIME: f32 = 1.0 / 30.0; // Seconds between recorded copter positions.
+pub const GHOST_ALPHA: f32 = 0.35;
+
+// Camera Effect Settings
+pub const SHAKE_MAX_OFFSET: f32 = 20.0;
+pub const SHAKE_MAX_ROTATION: f32 = 0.04;
+pub const SHAKE_TRAUMA_DECAY: f32 = 1.2; // Trauma lost per second.
+pub const CRASH_TRAUMA: f32 = 0.8;
+pub const ZOOM_PULSE_STRENGTH: f32 = 0.04; // Fraction of the view zoomed in at the peak of a pulse.
+pub const ZOOM_PULSE_TIME: f32 = 0.3;
+pub const CAMERA_FOLLOW_FACTOR: f32 = 0.15; // Fraction of the copter's y the camera drifts towards.
+pub const CAMERA_FOLLOW_SMOOTHING: f32 = 4.0;
+
+// Audio Settings
+pub const SAMPLE_RATE: u32 = 44_100;
+pub const MUSIC_CROSSFADE_TIME: f32 = 1.5;
+pub const ROTOR_PITCH_RANGE: f32 = 0.25; // Fraction the rotor speeds up or slows down at max velocity.
+pub const MAX_COPTER_VELOCITY: f32 = 500.0;
@@ -0,0 +1,449 @@
+use std::path::PathBuf;
+
+use bevy::{input::mouse::MouseWheel, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    components::{EditorText, MainCamera, Obstacle},
+    constants::{
+        EDITOR_HANDLE_RADIUS, EDITOR_MIN_OBSTACLE_SIZE, EDITOR_SCROLL_SPEED, EDITOR_WHEEL_STEP,
+    },
+    enums::{Action, AppState},
+    events::NewRunEvent,
+    levels::{course_distance, course_x, BorderKeyframe, Level, ObstaclePlacement},
+    pool::ColliderPool,
+    resources::{ActionState, GameRng, LevelProgress, LevelStart, Playfield},
+    settings::{binding_hint, Settings},
+    systems::bordertiles::spawn_full_border,
+    terrain::Terrain,
+};
+
+/*
+    Level editor:
+        The scroll stops, and the level is laid out around the copter, which
+        marks where test flights start from. Every handle can be dragged
+        with the left mouse button: the round ones of the border keyframes,
+        obstacles and the square handles on their corners that resize them,
+        the checkpoints and the finish line. Right clicking a handle deletes
+        what it belongs to, except for the finish line.
+
+            Left, Right, mouse wheel    scroll
+            B                           add a border keyframe at the cursor
+            O                           place an obstacle at the cursor
+            C                           place a checkpoint at the cursor
+            F                           move the finish line to the cursor
+            T                           test-fly from the copter
+            S                           save
+
+        The keys are the default bindings of the editor's actions, and can
+        be rebound like any other.
+
+        A test flight goes back to the editor wherever it would have gone
+        back to the main menu.
+*/
+
+// What the mouse holds on to.
+#[derive(Clone, Copy)]
+enum Grab {
+    Border { index: usize, top: bool },
+    Obstacle { index: usize, offset: Vec2 },
+    ObstacleSize { index: usize },
+    Checkpoint { index: usize },
+    Finish,
+}
+
+#[derive(Resource)]
+pub struct LevelEditor {
+    pub path: PathBuf,
+    grab: Option<Grab>,
+    // Where the mouse pointed last frame, so that holding a handle still doesn't edit anything.
+    cursor: Vec2,
+    saved: bool,
+}
+
+impl LevelEditor {
+    // Opens the level at a path, or starts a new one that is saved there.
+    pub fn open(path: PathBuf) -> Result<(Self, Level), String> {
+        let level = if path.exists() {
+            Level::load(&path)?
+        } else {
+            let name = path.file_stem().map_or("New Level".to_string(), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+            Level::new(name)
+        };
+
+        Ok((
+            Self {
+                path,
+                grab: None,
+                cursor: Vec2::ZERO,
+                saved: true,
+            },
+            level,
+        ))
+    }
+}
+
+// Playfield positions of the handles of the border keyframes.
+fn border_handles(
+    keyframe: &BorderKeyframe,
+    playfield: &Playfield,
+    progress: &LevelProgress,
+) -> [(Vec2, bool); 2] {
+    let x = course_x(keyframe.distance, progress);
+    [
+        (Vec2::new(x, playfield.half_height() - keyframe.top), true),
+        (
+            Vec2::new(x, -playfield.half_height() + keyframe.bottom),
+            false,
+        ),
+    ]
+}
+
+fn obstacle_center(obstacle: &ObstaclePlacement, progress: &LevelProgress) -> Vec2 {
+    Vec2::new(course_x(obstacle.distance, progress), obstacle.y)
+}
+
+fn obstacle_size_handle(obstacle: &ObstaclePlacement, progress: &LevelProgress) -> Vec2 {
+    obstacle_center(obstacle, progress) + obstacle.size * 0.5
+}
+
+// Handles win over the obstacles they lie on, and obstacles over the lines behind them.
+fn pick(
+    level: &Level,
+    playfield: &Playfield,
+    progress: &LevelProgress,
+    cursor: Vec2,
+) -> Option<Grab> {
+    let near = |handle: Vec2| handle.distance(cursor) <= EDITOR_HANDLE_RADIUS;
+
+    if let Some(index) = level
+        .obstacles
+        .iter()
+        .position(|obstacle| near(obstacle_size_handle(obstacle, progress)))
+    {
+        return Some(Grab::ObstacleSize { index });
+    }
+    for (index, keyframe) in level.borders.iter().enumerate() {
+        for (handle, top) in border_handles(keyframe, playfield, progress) {
+            if near(handle) {
+                return Some(Grab::Border { index, top });
+            }
+        }
+    }
+    if let Some((index, obstacle)) = level.obstacles.iter().enumerate().find(|(_, obstacle)| {
+        let offset = cursor - obstacle_center(obstacle, progress);
+        offset.abs().cmple(obstacle.size * 0.5).all()
+    }) {
+        return Some(Grab::Obstacle {
+            index,
+            offset: cursor - obstacle_center(obstacle, progress),
+        });
+    }
+    if let Some(index) = level.checkpoints.iter().position(|&checkpoint| {
+        (course_x(checkpoint, progress) - cursor.x).abs() <= EDITOR_HANDLE_RADIUS
+    }) {
+        return Some(Grab::Checkpoint { index });
+    }
+    if (course_x(level.finish, progress) - cursor.x).abs() <= EDITOR_HANDLE_RADIUS {
+        return Some(Grab::Finish);
+    }
+    None
+}
+
+fn drag(
+    grab: Grab,
+    level: &mut Level,
+    playfield: &Playfield,
+    progress: &LevelProgress,
+    cursor: Vec2,
+) {
+    let distance = course_distance(cursor.x, progress).max(0.0);
+    match grab {
+        Grab::Border { index, top } => {
+            // Keyframes can't be dragged past their neighbours, so they stay in order.
+            let previous = index
+                .checked_sub(1)
+                .and_then(|previous| level.borders.get(previous))
+                .map_or(0.0, |keyframe| keyframe.distance);
+            let next = level
+                .borders
+                .get(index + 1)
+                .map_or(f32::INFINITY, |keyframe| keyframe.distance);
+            let keyframe = &mut level.borders[index];
+            keyframe.distance = distance.clamp(previous, next);
+            if top {
+                keyframe.top = (playfield.half_height() - cursor.y).clamp(0.0, playfield.height);
+            } else {
+                keyframe.bottom = (cursor.y + playfield.half_height()).clamp(0.0, playfield.height);
+            }
+        }
+        Grab::Obstacle { index, offset } => {
+            let obstacle = &mut level.obstacles[index];
+            obstacle.distance = course_distance(cursor.x - offset.x, progress);
+            obstacle.y = cursor.y - offset.y;
+        }
+        Grab::ObstacleSize { index } => {
+            let obstacle = &mut level.obstacles[index];
+            obstacle.size = ((cursor - obstacle_center(obstacle, progress)) * 2.0)
+                .max(Vec2::splat(EDITOR_MIN_OBSTACLE_SIZE));
+        }
+        Grab::Checkpoint { index } => level.checkpoints[index] = distance,
+        Grab::Finish => level.finish = distance.max(EDITOR_HANDLE_RADIUS),
+    }
+}
+
+fn delete(grab: Grab, level: &mut Level) {
+    match grab {
+        Grab::Border { index, .. } => {
+            level.borders.remove(index);
+        }
+        Grab::Obstacle { index, .. } | Grab::ObstacleSize { index } => {
+            level.obstacles.remove(index);
+        }
+        Grab::Checkpoint { index } => {
+            level.checkpoints.remove(index);
+        }
+        // Every level needs an end.
+        Grab::Finish => {}
+    }
+}
+
+// Where the mouse points on the playfield, if it is over the window.
+fn cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn edit_level(
+    mouse: Res<ButtonInput<MouseButton>>,
+    action_state: Res<ActionState>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    playfield: Res<Playfield>,
+    progress: Res<LevelProgress>,
+    mut editor: ResMut<LevelEditor>,
+    mut level: ResMut<Level>,
+) {
+    let Some(cursor) = cursor_position(&window_query, &camera_query) else {
+        return;
+    };
+    let distance = course_distance(cursor.x, &progress).max(0.0);
+
+    if mouse.just_pressed(MouseButton::Left) {
+        editor.grab = pick(&level, &playfield, &progress, cursor);
+    }
+    let edited = if let Some(grab) = editor.grab {
+        if !mouse.pressed(MouseButton::Left) {
+            editor.grab = None;
+            level.sort();
+            false
+        } else if cursor != editor.cursor {
+            drag(grab, &mut level, &playfield, &progress, cursor);
+            true
+        } else {
+            false
+        }
+    } else if mouse.just_pressed(MouseButton::Right) {
+        pick(&level, &playfield, &progress, cursor)
+            .map(|grab| delete(grab, &mut level))
+            .is_some()
+    } else if action_state.just_pressed(Action::PlaceBorder) {
+        let heights = level.borders_at(distance);
+        level.borders.push(BorderKeyframe {
+            distance,
+            top: heights.top_border,
+            bottom: heights.bottom_border,
+        });
+        true
+    } else if action_state.just_pressed(Action::PlaceObstacle) {
+        level.obstacles.push(ObstaclePlacement {
+            distance,
+            y: cursor.y,
+            size: ObstaclePlacement::default_size(),
+        });
+        true
+    } else if action_state.just_pressed(Action::PlaceCheckpoint) {
+        level.checkpoints.push(distance);
+        true
+    } else if action_state.just_pressed(Action::PlaceFinish) {
+        level.finish = distance.max(EDITOR_HANDLE_RADIUS);
+        true
+    } else {
+        false
+    };
+    editor.cursor = cursor;
+
+    if edited {
+        editor.saved = false;
+        // Held handles are put in order once they are let go.
+        if editor.grab.is_none() {
+            level.sort();
+        }
+    }
+
+    if action_state.just_pressed(Action::SaveLevel) {
+        match level.save(&editor.path) {
+            Ok(()) => editor.saved = true,
+            Err(err) => warn!("Failed to save {}: {err}", editor.path.display()),
+        }
+    }
+}
+
+pub fn scroll_editor(
+    time: Res<Time<Real>>,
+    action_state: Res<ActionState>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    let mut scroll: f32 = mouse_wheel_events
+        .read()
+        .map(|event| event.y * EDITOR_WHEEL_STEP)
+        .sum();
+    if action_state.pressed(Action::ScrollRight) {
+        scroll += EDITOR_SCROLL_SPEED * time.delta_secs();
+    }
+    if action_state.pressed(Action::ScrollLeft) {
+        scroll -= EDITOR_SCROLL_SPEED * time.delta_secs();
+    }
+
+    if scroll != 0.0 {
+        progress.distance = (progress.distance + scroll).max(0.0);
+    }
+}
+
+// Lays the level out again whenever it was edited or scrolled.
+#[allow(clippy::too_many_arguments)]
+pub fn rebuild_course(
+    mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
+    mut terrain: ResMut<Terrain>,
+    level: Res<Level>,
+    progress: Res<LevelProgress>,
+    playfield: Res<Playfield>,
+    settings: Res<Settings>,
+    mut obstacle_query: Query<(Entity, &mut Transform, &mut Sprite), With<Obstacle>>,
+) {
+    if !level.is_changed() && !progress.is_changed() {
+        return;
+    }
+
+    spawn_full_border(&mut terrain, &playfield, Some(&level), &progress);
+    let on_screen = level.obstacles.iter().filter_map(|obstacle| {
+        let x = course_x(obstacle.distance, &progress);
+        ((x.abs() - obstacle.size.x * 0.5) < playfield.half_width())
+            .then(|| (Vec3::new(x, obstacle.y, 1.0), obstacle.size))
+    });
+    pool.lay_out(
+        &mut commands,
+        &mut obstacle_query,
+        on_screen,
+        settings.display.color_theme.wall(),
+    );
+}
+
+pub fn draw_editor_handles(
+    mut gizmos: Gizmos,
+    level: Res<Level>,
+    progress: Res<LevelProgress>,
+    playfield: Res<Playfield>,
+) {
+    let handle_color = Color::srgb(1.0, 0.85, 0.3);
+
+    for keyframe in &level.borders {
+        for (handle, _) in border_handles(keyframe, &playfield, &progress) {
+            gizmos.circle_2d(
+                Isometry2d::from_translation(handle),
+                EDITOR_HANDLE_RADIUS,
+                handle_color,
+            );
+        }
+    }
+    for obstacle in &level.obstacles {
+        gizmos.rect_2d(
+            Isometry2d::from_translation(obstacle_size_handle(obstacle, &progress)),
+            Vec2::splat(EDITOR_HANDLE_RADIUS * 2.0),
+            handle_color,
+        );
+    }
+}
+
+pub fn setup_editor_text(mut commands: Commands) {
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..Default::default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            left: Val::Px(10.0),
+            ..Default::default()
+        },
+        EditorText,
+        StateScoped(AppState::Editing),
+    ));
+}
+
+pub fn update_editor_text(
+    editor: Res<LevelEditor>,
+    settings: Res<Settings>,
+    level: Res<Level>,
+    progress: Res<LevelProgress>,
+    mut text_query: Query<&mut Text, With<EditorText>>,
+) {
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let hint = |action| binding_hint(&settings, action);
+    text.0 = format!(
+        "{}{} - {}\nDistance: {:.0} of {:.0}\n\nDrag handles with the mouse, right click deletes\n{} border  {} obstacle  {} checkpoint  {} finish\n{} {} or wheel scroll  {} test-fly  {} save",
+        level.name,
+        if editor.saved { "" } else { " (unsaved)" },
+        editor.path.display(),
+        progress.distance,
+        level.finish,
+        hint(Action::PlaceBorder),
+        hint(Action::PlaceObstacle),
+        hint(Action::PlaceCheckpoint),
+        hint(Action::PlaceFinish),
+        hint(Action::ScrollLeft),
+        hint(Action::ScrollRight),
+        hint(Action::TestFly),
+        hint(Action::SaveLevel),
+    );
+}
+
+// Test flights start where the editor is scrolled to.
+pub fn start_test_flight(
+    action_state: Res<ActionState>,
+    progress: Res<LevelProgress>,
+    mut level_start: ResMut<LevelStart>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    if action_state.just_pressed(Action::TestFly) {
+        level_start.0 = progress.distance;
+        next_app_state.set(AppState::Playing);
+    }
+}
+
+/*
+    Entering the editor puts the world back where the test flight started,
+    and leaving it starts the test flight. Resetting only once the state
+    changed keeps the editor from laying out a world the run then spawns
+    all over again.
+*/
+pub fn reset_course(rng: Res<GameRng>, mut new_run_events: EventWriter<NewRunEvent>) {
+    new_run_events.send(NewRunEvent {
+        seed: rng.seed,
+        players: 1,
+    });
+}
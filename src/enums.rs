@@ -1,4 +1,256 @@
+use bevy::prelude::{Color, GamepadButton, KeyCode, MouseButton, States};
+use serde::{Deserialize, Serialize};
+
+use crate::daily;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub enum FluctuatingDirection {
     Up,
     Down,
 }
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Music {
+    Menu,
+    Gameplay,
+}
+
+// What the gamer wants to do, independent of the device they used to say it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Lift,
+    // Lift of the second copter in versus.
+    LiftPlayerTwo,
+    Pause,
+    Restart,
+    Confirm,
+    Back,
+    ToggleFullscreen,
+    QuickSave,
+    QuickLoad,
+    // Level editor.
+    PlaceBorder,
+    PlaceObstacle,
+    PlaceCheckpoint,
+    PlaceFinish,
+    ScrollLeft,
+    ScrollRight,
+    TestFly,
+    SaveLevel,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Lift,
+        Action::LiftPlayerTwo,
+        Action::Pause,
+        Action::Restart,
+        Action::Confirm,
+        Action::Back,
+        Action::ToggleFullscreen,
+        Action::QuickSave,
+        Action::QuickLoad,
+        Action::PlaceBorder,
+        Action::PlaceObstacle,
+        Action::PlaceCheckpoint,
+        Action::PlaceFinish,
+        Action::ScrollLeft,
+        Action::ScrollRight,
+        Action::TestFly,
+        Action::SaveLevel,
+    ];
+}
+
+// A single physical input that can be bound to an action.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl std::fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            InputBinding::Key(key) => write!(f, "{key:?}"),
+            InputBinding::Mouse(button) => write!(f, "Mouse {button:?}"),
+            InputBinding::Gamepad(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+// How the playfield fills a window whose aspect ratio does not match it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum PlayfieldScaling {
+    // Black bars hide everything outside the playfield.
+    #[default]
+    Letterbox,
+    // The playfield grows horizontally to fill wide windows.
+    Extend,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ColorTheme {
+    #[default]
+    Classic,
+    HighContrast,
+    Dusk,
+}
+
+impl ColorTheme {
+    pub const ALL: [ColorTheme; 3] = [
+        ColorTheme::Classic,
+        ColorTheme::HighContrast,
+        ColorTheme::Dusk,
+    ];
+
+    pub fn background(&self) -> Color {
+        match self {
+            ColorTheme::Classic => Color::srgb(0.1, 0.1, 0.2),
+            ColorTheme::HighContrast => Color::BLACK,
+            ColorTheme::Dusk => Color::srgb(0.22, 0.1, 0.2),
+        }
+    }
+
+    // Odd players share the colors of player one, even ones those of player two.
+    pub fn copter(&self, player: Player) -> Color {
+        match (self, player.0.is_multiple_of(2)) {
+            (ColorTheme::Classic, true) => Color::srgb(0.8, 0.3, 0.3),
+            (ColorTheme::Classic, false) => Color::srgb(0.3, 0.5, 0.9),
+            (ColorTheme::HighContrast, true) => Color::srgb(1.0, 0.9, 0.0),
+            (ColorTheme::HighContrast, false) => Color::srgb(0.0, 0.9, 1.0),
+            (ColorTheme::Dusk, true) => Color::srgb(0.95, 0.75, 0.45),
+            (ColorTheme::Dusk, false) => Color::srgb(0.55, 0.85, 0.8),
+        }
+    }
+
+    pub fn wall(&self) -> Color {
+        match self {
+            ColorTheme::Classic => Color::srgb(0.3, 0.8, 0.3),
+            ColorTheme::HighContrast => Color::WHITE,
+            ColorTheme::Dusk => Color::srgb(0.45, 0.3, 0.55),
+        }
+    }
+}
+
+// Whether a run is being played, the game idles behind the main menu, or a level is being edited.
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    Playing,
+    // The world stands still while the level editor lays it out.
+    Editing,
+}
+
+// Menu screen shown on top of the game. While playing, everything but `Closed` pauses the run.
+#[derive(States, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum MenuScreen {
+    Closed,
+    #[default]
+    Main,
+    GameModes,
+    HighScores,
+    Pause,
+    Settings,
+    Bindings,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum GameMode {
+    #[default]
+    Endless,
+    Daily,
+    Versus,
+    Practice,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Endless,
+        GameMode::Daily,
+        GameMode::Versus,
+        GameMode::Practice,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            GameMode::Endless => "Fly as far as you can through a random cave.",
+            GameMode::Daily => "Everyone flies the same cave today. The first try is official.",
+            GameMode::Versus => "Two copters, one cave. The last one flying wins.",
+            GameMode::Practice => "Crashing puts you back a few seconds instead of at the start.",
+        }
+    }
+
+    pub fn players(&self) -> usize {
+        match self {
+            GameMode::Endless | GameMode::Daily | GameMode::Practice => 1,
+            GameMode::Versus => 2,
+        }
+    }
+
+    // Daily runs only compete with the runs of the same day.
+    pub fn leaderboard_day(&self) -> Option<u64> {
+        match self {
+            GameMode::Endless | GameMode::Versus | GameMode::Practice => None,
+            GameMode::Daily => Some(daily::today()),
+        }
+    }
+
+    // Versus runs are won against the other gamer, and practice runs are flown in pieces.
+    pub fn ranked(&self) -> bool {
+        match self {
+            GameMode::Endless | GameMode::Daily => true,
+            GameMode::Versus | GameMode::Practice => false,
+        }
+    }
+}
+
+// Gamer flying a copter, counted from zero. Solo runs only have player one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub struct Player(pub usize);
+
+impl Player {
+    pub const ONE: Player = Player(0);
+    pub const TWO: Player = Player(1);
+
+    pub fn name(&self) -> String {
+        format!("Player {}", self.0 + 1)
+    }
+
+    // Only the first two players can share a keyboard.
+    pub fn lift(&self) -> Option<Action> {
+        match *self {
+            Player::ONE => Some(Action::Lift),
+            Player::TWO => Some(Action::LiftPlayerTwo),
+            _ => None,
+        }
+    }
+}
+
+pub enum MenuInput {
+    Activate,
+    Decrease,
+    Increase,
+}
+
+// What the copter crashed into.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrashCause {
+    Obstacle,
+    TopBorder,
+    BottomBorder,
+}
+
+// Bots that can fly headless runs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotKind {
+    #[default]
+    Autopilot,
+    // Never lifts, so it shows how long the cave lets a copter simply fall.
+    Idle,
+    // Holds the middle of the playfield and ignores everything else.
+    Hover,
+}
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+
+use crate::enums::MenuInput;
+
+// Sent once for every copter that crashes into an obstacle or a wall.
+#[derive(Event)]
+pub struct CrashEvent;
+
+// Sent when the copter squeezes past an obstacle or a wall without touching it.
+#[derive(Event)]
+pub struct NearMissEvent {
+    pub position: Vec3,
+    pub points: u32,
+}
+
+// Sent when a menu item is activated or adjusted, by mouse, keyboard or gamepad.
+#[derive(Event)]
+pub struct MenuEvent {
+    pub item: Entity,
+    pub input: MenuInput,
+}
+
+// Clears the world and starts a fresh run, used by restarts and the menus.
+#[derive(Event)]
+pub struct NewRunEvent {
+    // Runs with the same seed fly through the same course.
+    pub seed: u64,
+    // Number of copters, each flown by its own gamer or bot.
+    pub players: usize,
+}
@@ -1,95 +1,294 @@
-use bevy::prelude::*;
+use bevy::{
+    audio::AddAudioSource, diagnostic::FrameTimeDiagnosticsPlugin, input::InputSystem, prelude::*,
+};
 
 use crate::{
-    components::{BorderTile, Copter, Obstacle},
-    constants::{COPTER_SIZE, WINDOW_HEIGHT, WINDOW_WIDTH},
-    resources::{BorderTileCurrentHeight, BorderTileFluctuator, GameState},
+    broadcast::{broadcast_world, mirror_broadcast, stop_spectating, Broadcast, Spectator},
+    components::{Background, Letterbox, MainCamera},
+    constants::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH},
+    editor::{
+        draw_editor_handles, edit_level, rebuild_course, reset_course, scroll_editor,
+        setup_editor_text, start_test_flight, update_editor_text,
+    },
+    enums::{AppState, MenuScreen},
+    events::MenuEvent,
+    ghosts::{
+        fly_ghost, record_trajectory, save_ghost, spawn_ghost, start_ghost_race, GhostRace, Ghosts,
+    },
+    highscores::{record_high_score, start_official_attempt, HighScores, OfficialAttempt},
+    lan::{
+        begin_lockstep, leave_lan_race, lockstep_lift, lockstep_ready, show_lan_opponents,
+        start_lan_race, LanSession,
+    },
+    practice::{
+        forget_practice_checkpoints, practicing, return_to_practice_checkpoint,
+        take_practice_checkpoint, Practice,
+    },
+    resources::{AttractMode, CameraEffects, CameraEffectsSettings, MenuSelection, Rebinding},
+    settings::{save_settings, Settings},
+    simulation::{SimulationPlugin, SimulationSet},
+    snapshot::{quick_load, quick_save},
     systems::{
-        bordertiles::{bordertile_movement, spawn_bordertiles, spawn_init_border},
+        audio::{crossfade_music, play_sound_effects, rotor_sound, setup_audio, Synth},
+        autopilot::{attract_mode, hand_over_to_autopilot, hand_over_to_gamer},
+        camera::{apply_camera_effects, trigger_camera_effects},
         collision::collision_detection,
-        copter::copter_movement,
-        obstacles::{obstacle_movement, spawn_obstacles},
-        ui::{setup_ui, update_score},
+        copter::{copter_movement, gamer_lift},
+        display::{
+            apply_display_settings, apply_window_settings, fit_playfield, toggle_fullscreen,
+        },
+        input::update_action_state,
+        main_menu::{
+            game_modes_menu, high_scores_menu, leave_finished_run, main_menu,
+            setup_game_modes_menu, setup_high_scores_menu, setup_main_menu,
+        },
+        menu::{
+            highlight_menu_items, navigate_menu, open_pause_menu, pause_in_menus, pause_menu,
+            reset_menu_selection, setup_pause_menu,
+        },
+        rebinding::{bindings_menu, capture_binding, setup_bindings_menu},
+        settings_menu::{settings_menu, setup_settings_menu},
+        ui::{
+            floating_labels, setup_ui, spawn_near_miss_labels, update_fps_text,
+            update_instructions_text, update_score_breakdown, update_score_text,
+        },
     },
+    terrain::{draw_walls, spawn_walls},
 };
 
+// Everything the gamer sees, hears and touches, on top of the simulation.
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(GameState::default())
-            .insert_resource(BorderTileCurrentHeight::default())
-            .insert_resource(BorderTileFluctuator::default())
-            .add_systems(Startup, (setup_game, setup_ui, spawn_init_border))
+        app.add_plugins(SimulationPlugin)
+            .insert_resource(CameraEffectsSettings::default())
+            .insert_resource(CameraEffects::default())
+            .insert_resource(Rebinding::default())
+            .insert_resource(MenuSelection::default())
+            .insert_resource(AttractMode::default())
+            .insert_resource(HighScores::load())
+            .insert_resource(OfficialAttempt::default())
+            .insert_resource(Ghosts::load())
+            .insert_resource(GhostRace::default())
+            .insert_resource(Practice::default())
+            .init_state::<MenuScreen>()
+            .enable_state_scoped_entities::<MenuScreen>()
+            .enable_state_scoped_entities::<AppState>()
+            .add_event::<MenuEvent>()
+            .add_audio_source::<Synth>()
+            .add_plugins(FrameTimeDiagnosticsPlugin)
+            .add_systems(
+                Startup,
+                (
+                    (setup_game, fit_playfield).chain(),
+                    setup_ui,
+                    setup_audio,
+                    spawn_ghost,
+                    spawn_walls,
+                ),
+            )
+            .add_systems(PreUpdate, update_action_state.after(InputSystem))
+            // LAN races wait for the lift of every player, and can't be restarted by one of them.
+            // Spectators only mirror the world of someone else, and the world stands still in the editor.
+            .configure_sets(
+                FixedUpdate,
+                SimulationSet::Step.run_if(
+                    lockstep_ready
+                        .and(not(resource_exists::<Spectator>))
+                        .and(not(in_state(AppState::Editing))),
+                ),
+            )
+            .configure_sets(
+                Update,
+                SimulationSet::Restart.run_if(
+                    not(resource_exists::<LanSession>).and(not(resource_exists::<Spectator>)),
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                lockstep_lift
+                    .after(gamer_lift)
+                    .before(copter_movement)
+                    .in_set(SimulationSet::Step)
+                    .run_if(resource_exists::<LanSession>),
+            )
+            // Practice runs fly on from their latest checkpoint after a crash.
+            .add_systems(
+                FixedUpdate,
+                take_practice_checkpoint
+                    .after(collision_detection)
+                    .in_set(SimulationSet::Step)
+                    .run_if(in_state(AppState::Playing).and(practicing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    forget_practice_checkpoints,
+                    return_to_practice_checkpoint
+                        .run_if(in_state(AppState::Playing).and(practicing)),
+                    (quick_save, quick_load).run_if(
+                        in_state(AppState::Playing)
+                            .and(not(resource_exists::<LanSession>))
+                            .and(not(resource_exists::<Spectator>)),
+                    ),
+                )
+                    .chain()
+                    .after(SimulationSet::Restart),
+            )
+            .add_systems(
+                Startup,
+                start_lan_race.run_if(resource_exists::<LanSession>),
+            )
+            .add_systems(Update, begin_lockstep.run_if(resource_exists::<LanSession>))
+            .add_systems(
+                Update,
+                (
+                    broadcast_world.run_if(resource_exists::<Broadcast>),
+                    mirror_broadcast.run_if(resource_exists::<Spectator>),
+                ),
+            )
+            .add_systems(
+                OnEnter(AppState::MainMenu),
+                (hand_over_to_autopilot, leave_lan_race, stop_spectating),
+            )
+            .add_systems(
+                OnEnter(AppState::Editing),
+                (reset_course, setup_editor_text),
+            )
+            .add_systems(OnExit(AppState::Editing), reset_course)
             .add_systems(
                 Update,
                 (
-                    copter_movement,
-                    spawn_obstacles,
-                    spawn_bordertiles,
-                    obstacle_movement,
-                    bordertile_movement,
-                    collision_detection,
-                    update_score,
-                    restart,
+                    scroll_editor,
+                    edit_level,
+                    start_test_flight,
+                    draw_editor_handles,
+                    update_editor_text,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Editing)),
+            )
+            .add_systems(
+                OnEnter(AppState::Playing),
+                (hand_over_to_gamer, start_official_attempt),
+            )
+            .add_systems(OnEnter(MenuScreen::Main), setup_main_menu)
+            .add_systems(OnEnter(MenuScreen::GameModes), setup_game_modes_menu)
+            .add_systems(OnEnter(MenuScreen::HighScores), setup_high_scores_menu)
+            .add_systems(OnEnter(MenuScreen::Pause), setup_pause_menu)
+            .add_systems(OnEnter(MenuScreen::Settings), setup_settings_menu)
+            .add_systems(OnEnter(MenuScreen::Bindings), setup_bindings_menu)
+            .add_systems(
+                Update,
+                (
+                    open_pause_menu.run_if(in_state(AppState::Playing)),
+                    leave_finished_run.run_if(in_state(AppState::Playing)),
+                    pause_in_menus,
+                    reset_menu_selection.run_if(state_changed::<MenuScreen>),
+                    capture_binding,
+                    navigate_menu,
+                    (
+                        main_menu.run_if(in_state(MenuScreen::Main)),
+                        game_modes_menu.run_if(in_state(MenuScreen::GameModes)),
+                        high_scores_menu.run_if(in_state(MenuScreen::HighScores)),
+                        pause_menu.run_if(in_state(MenuScreen::Pause)),
+                        settings_menu.run_if(in_state(MenuScreen::Settings)),
+                        bindings_menu.run_if(in_state(MenuScreen::Bindings)),
+                    ),
+                    highlight_menu_items,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_score_text,
+                    update_score_breakdown,
+                    spawn_near_miss_labels,
+                    floating_labels,
+                    attract_mode,
+                    record_high_score.run_if(in_state(AppState::Playing)),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    start_ghost_race,
+                    record_trajectory.run_if(in_state(AppState::Playing)),
+                    fly_ghost,
+                    save_ghost,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    rotor_sound,
+                    play_sound_effects,
+                    crossfade_music,
+                    save_settings,
                 ),
             )
-            .add_systems(FixedUpdate, (spawn_bordertiles,));
+            .add_systems(
+                Update,
+                (
+                    toggle_fullscreen,
+                    apply_window_settings,
+                    apply_display_settings,
+                    show_lan_opponents.run_if(resource_exists::<LanSession>),
+                    fit_playfield,
+                    update_fps_text,
+                    update_instructions_text,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                PostUpdate,
+                (trigger_camera_effects, apply_camera_effects)
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            )
+            // After the runs reset in Update, so that the editor has the last word on the world.
+            .add_systems(
+                PostUpdate,
+                rebuild_course
+                    .run_if(in_state(AppState::Editing))
+                    .before(TransformSystem::TransformPropagate),
+            )
+            // Once the world is final for the frame.
+            .add_systems(PostUpdate, draw_walls.after(rebuild_course));
     }
 }
 
-// Setup the camera, background, helicopter and UI elements.
-fn setup_game(mut commands: Commands) {
+// Setup the camera and background. The copter is spawned by the simulation.
+fn setup_game(mut commands: Commands, settings: Res<Settings>) {
     // Camera.
-    commands.spawn(Camera2d::default());
+    commands.spawn((Camera2d, MainCamera));
 
-    // Background.
+    // Background. Sized to the playfield by `fit_playfield`.
     commands.spawn((
         Sprite {
-            color: Color::srgb(0.1, 0.1, 0.2),
-            custom_size: Some(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT)),
+            color: settings.display.color_theme.background(),
+            custom_size: Some(Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT) * 1.5),
             ..Default::default()
         },
         Transform::from_xyz(0.0, 0.0, 0.0),
+        Background,
     ));
 
-    // Copter.
-    commands.spawn((
-        Sprite {
-            color: Color::srgb(0.8, 0.3, 0.3),
-            custom_size: Some(COPTER_SIZE),
-            ..Default::default()
-        },
-        Transform::from_xyz(-300.0, 0.0, 1.0),
-        Copter { velocity: 0.0 },
-    ));
-}
-
-fn restart(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut game_state: ResMut<GameState>,
-    mut commands: Commands,
-    obstacle_query: Query<Entity, With<Obstacle>>,
-    bordertile_query: Query<Entity, With<BorderTile>>,
-    mut copter_query: Query<(&mut Copter, &mut Transform)>,
-) {
-    if game_state.game_over && keyboard_input.just_pressed(KeyCode::KeyR) {
-        game_state.game_over = false;
-        game_state.score = 0.0;
-
-        for entity in obstacle_query.iter() {
-            commands.entity(entity).despawn();
-        }
-        for entity in bordertile_query.iter() {
-            commands.entity(entity).despawn();
-        }
-
-        // Important to respawn obstacles once gamer restarts.
-        game_state.obstacle_timer.reset();
-
-        if let Ok((mut copter, mut transform)) = copter_query.get_single_mut() {
-            copter.velocity = 0.0;
-            transform.translation = Vec3::new(-300.0, 0.0, 1.0);
-        }
+    // Letterbox bars, one on every side of the playfield.
+    let bar_size = Vec2::splat(PLAYFIELD_WIDTH.max(PLAYFIELD_HEIGHT) * 4.0);
+    for direction in [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y] {
+        let offset = direction * (Vec2::new(PLAYFIELD_WIDTH, PLAYFIELD_HEIGHT) + bar_size) * 0.5;
+        commands.spawn((
+            Sprite {
+                color: Color::BLACK,
+                custom_size: Some(bar_size),
+                ..Default::default()
+            },
+            Transform::from_translation(offset.extend(10.0)),
+            Letterbox,
+        ));
     }
 }
@@ -0,0 +1,211 @@
+use std::{
+    cmp::Reverse,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Copter, Ghost, Score},
+    constants::{COPTER_SIZE, COPTER_START_X, GHOST_ALPHA, GHOST_SAMPLE_TIME},
+    enums::{AppState, GameMode, Player},
+    events::{CrashEvent, NewRunEvent},
+    levels::Level,
+    resources::{GameRng, GameState, ScoreWeights, SelectedGameMode},
+    settings::Settings,
+    storage::{load_ron, save_ron},
+};
+
+const MAX_GHOSTS: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GhostRun {
+    pub seed: u64,
+    pub score: u32,
+    // Seconds since the Unix epoch.
+    pub achieved_at: u64,
+    // Copter y every GHOST_SAMPLE_TIME seconds, until the crash.
+    pub trajectory: Vec<f32>,
+}
+
+// Best run on every recently flown course, persisted as RON in the user's data directory.
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Ghosts {
+    pub runs: Vec<GhostRun>,
+}
+
+fn ghosts_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("copter").join("ghosts.ron"))
+}
+
+impl Ghosts {
+    pub fn load() -> Self {
+        load_ron(ghosts_path().as_deref())
+    }
+
+    pub fn save(&self) {
+        save_ron(ghosts_path().as_deref(), self);
+    }
+
+    pub fn best(&self, seed: u64) -> Option<&GhostRun> {
+        self.runs.iter().find(|run| run.seed == seed)
+    }
+
+    // Keeps the run if it beats the ghost of its course. Returns whether it did.
+    pub fn insert(&mut self, run: GhostRun) -> bool {
+        if self
+            .best(run.seed)
+            .is_some_and(|best| best.score >= run.score)
+        {
+            return false;
+        }
+
+        self.runs.retain(|best| best.seed != run.seed);
+        self.runs.push(run);
+        // Only the most recently beaten courses are worth racing again.
+        self.runs.sort_by_key(|run| Reverse(run.achieved_at));
+        self.runs.truncate(MAX_GHOSTS);
+        true
+    }
+}
+
+// The run being recorded and the ghost it races against.
+#[derive(Resource, Default)]
+pub struct GhostRace {
+    pub elapsed: f32,
+    pub recording: Vec<f32>,
+    pub ghost: Option<Vec<f32>>,
+}
+
+impl GhostRace {
+    // Where the ghost was at the current time, or None once it has crashed.
+    pub fn ghost_y(&self) -> Option<f32> {
+        let trajectory = self.ghost.as_ref()?;
+        let position = self.elapsed / GHOST_SAMPLE_TIME;
+        let index = position as usize;
+        let (from, to) = (trajectory.get(index)?, trajectory.get(index + 1)?);
+        Some(from.lerp(*to, position.fract()))
+    }
+}
+
+// The ghost has no Copter component, so nothing collides with it.
+pub fn spawn_ghost(mut commands: Commands) {
+    commands.spawn((
+        Sprite {
+            custom_size: Some(COPTER_SIZE),
+            ..Default::default()
+        },
+        Transform::from_xyz(COPTER_START_X, 0.0, 0.9),
+        Visibility::Hidden,
+        Ghost,
+    ));
+}
+
+pub fn start_ghost_race(
+    mut new_run_events: EventReader<NewRunEvent>,
+    ghosts: Res<Ghosts>,
+    mut race: ResMut<GhostRace>,
+) {
+    // Versus runs already have a rival to race.
+    if let Some(event) = new_run_events.read().last() {
+        *race = GhostRace {
+            ghost: ghosts
+                .best(event.seed)
+                .filter(|_| event.players == 1)
+                .map(|run| run.trajectory.clone()),
+            ..Default::default()
+        };
+    }
+}
+
+pub fn record_trajectory(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    copter_query: Query<(&Copter, &Transform)>,
+    mut race: ResMut<GhostRace>,
+) {
+    if game_state.game_over {
+        return;
+    }
+    let Some((_, copter_transform)) = copter_query
+        .iter()
+        .find(|(copter, _)| copter.player == Player::ONE)
+    else {
+        return;
+    };
+
+    race.elapsed += time.delta_secs();
+    while race.recording.len() as f32 * GHOST_SAMPLE_TIME <= race.elapsed {
+        race.recording.push(copter_transform.translation.y);
+    }
+}
+
+pub fn fly_ghost(
+    app_state: Res<State<AppState>>,
+    settings: Res<Settings>,
+    race: Res<GhostRace>,
+    mut ghost_query: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<Ghost>>,
+) {
+    let ghost_y = race
+        .ghost_y()
+        .filter(|_| *app_state.get() == AppState::Playing);
+
+    for (mut transform, mut sprite, mut visibility) in ghost_query.iter_mut() {
+        sprite.color = settings
+            .display
+            .color_theme
+            .copter(Player::ONE)
+            .with_alpha(GHOST_ALPHA);
+
+        match ghost_y {
+            Some(y) => {
+                transform.translation.y = y;
+                *visibility = Visibility::Inherited;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn save_ghost(
+    mut crash_events: EventReader<CrashEvent>,
+    app_state: Res<State<AppState>>,
+    rng: Res<GameRng>,
+    selected_mode: Res<SelectedGameMode>,
+    level: Option<Res<Level>>,
+    score_query: Query<&Score, With<Copter>>,
+    score_weights: Res<ScoreWeights>,
+    race: Res<GhostRace>,
+    mut ghosts: ResMut<Ghosts>,
+) {
+    // Crashes of the attract mode demo don't count.
+    if crash_events.read().count() == 0 || *app_state.get() != AppState::Playing {
+        return;
+    }
+    // Ghosts are kept by seed, which means nothing on a level, and practice runs jump back in time.
+    if level.is_some() || selected_mode.0 == GameMode::Practice {
+        return;
+    }
+    // Only solo runs leave a ghost.
+    let Ok(score) = score_query.get_single() else {
+        return;
+    };
+
+    let achieved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let beaten = ghosts.insert(GhostRun {
+        seed: rng.seed,
+        score: score.total(&score_weights),
+        achieved_at,
+        trajectory: race.recording.clone(),
+    });
+    if beaten {
+        ghosts.save();
+    }
+}
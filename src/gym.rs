@@ -0,0 +1,203 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use bevy::{ecs::system::SystemState, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bot::{Observation, Observer},
+    components::Score,
+    constants::GYM_CRASH_REWARD,
+    headless::headless_app,
+    resources::{GameState, ScoreWeights},
+};
+
+/*
+    Gym-style environment over a local socket, spoken in JSON lines:
+
+        -> {"cmd": "reset", "seed": 42}
+        <- {"observation": {...}, "reward": 0.0, "done": false, "score": 0}
+        -> {"cmd": "step", "lift": true}
+        <- {"observation": {...}, "reward": 1.0, "done": false, "score": 1}
+
+    Every step advances the headless game by one fixed tick. The reward is
+    the score gained during the tick, and GYM_CRASH_REWARD on the tick the
+    copter crashes, which also ends the episode. Every connection gets its
+    own game.
+*/
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Reset {
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    Step {
+        lift: bool,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+    Transition {
+        observation: Observation,
+        reward: f32,
+        done: bool,
+        score: u32,
+    },
+    Error {
+        error: String,
+    },
+}
+
+pub struct GymEnv {
+    app: App,
+    lift: Arc<AtomicBool>,
+    score: u32,
+}
+
+impl GymEnv {
+    pub fn new(seed: u64) -> Self {
+        let lift = Arc::new(AtomicBool::new(false));
+        let decision = lift.clone();
+        let mut app = headless_app(
+            seed,
+            Box::new(move |_: &Observation| decision.load(Ordering::Relaxed)),
+        );
+        // Runs the startup systems, so there is a copter to observe.
+        app.update();
+
+        Self {
+            app,
+            lift,
+            score: 0,
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.app.world().resource::<GameState>().game_over
+    }
+
+    pub fn score(&mut self) -> u32 {
+        let world = self.app.world_mut();
+        let mut score_query = world.query::<&Score>();
+        score_query
+            .get_single(world)
+            .map_or(0, |score| score.total(world.resource::<ScoreWeights>()))
+    }
+
+    pub fn observe(&mut self) -> Option<Observation> {
+        let mut state = SystemState::<Observer>::new(self.app.world_mut());
+        state.get(self.app.world()).observe()
+    }
+
+    // Advances one tick and returns the reward for it.
+    pub fn step(&mut self, lift: bool) -> f32 {
+        self.lift.store(lift, Ordering::Relaxed);
+        self.app.update();
+
+        let score = self.score();
+        let reward = score.saturating_sub(self.score) as f32;
+        self.score = score;
+
+        if self.done() {
+            GYM_CRASH_REWARD
+        } else {
+            reward
+        }
+    }
+}
+
+fn transition(env: &mut GymEnv, reward: f32) -> Response {
+    match env.observe() {
+        Some(observation) => Response::Transition {
+            observation,
+            reward,
+            done: env.done(),
+            score: env.score,
+        },
+        None => Response::Error {
+            error: "the copter is missing".to_string(),
+        },
+    }
+}
+
+fn handle_request(env: &mut Option<GymEnv>, line: &str) -> Response {
+    let request = match serde_json::from_str::<Request>(line) {
+        Ok(request) => request,
+        Err(error) => {
+            return Response::Error {
+                error: error.to_string(),
+            }
+        }
+    };
+
+    match request {
+        Request::Reset { seed } => {
+            let env = env.insert(GymEnv::new(seed.unwrap_or_else(rand::random)));
+            transition(env, 0.0)
+        }
+        Request::Step { lift } => match env {
+            Some(env) if !env.done() => {
+                let reward = env.step(lift);
+                transition(env, reward)
+            }
+            Some(_) => Response::Error {
+                error: "the episode is over, reset first".to_string(),
+            },
+            None => Response::Error {
+                error: "no episode yet, reset first".to_string(),
+            },
+        },
+    }
+}
+
+fn handle_connection(stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut env = None;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = handle_request(&mut env, &line);
+        serde_json::to_writer(&mut writer, &response)?;
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+// Serves environments until the process is killed.
+pub fn serve(address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    eprintln!("Gym environment listening on {}", listener.local_addr()?);
+
+    for stream in listener.incoming() {
+        // A failed accept only loses that connection, the others are still served.
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                eprintln!("Could not accept a gym connection: {error}");
+                continue;
+            }
+        };
+        thread::spawn(move || {
+            if let Err(error) = handle_connection(stream) {
+                eprintln!("Gym connection closed: {error}");
+            }
+        });
+    }
+
+    Ok(())
+}
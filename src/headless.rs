@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy};
+use serde::Serialize;
+
+use crate::{
+    bot::{BotPilot, Controller},
+    components::{Copter, Score},
+    constants::SIMULATION_TIMESTEP,
+    enums::{AppState, CrashCause},
+    resources::{GameRng, GameState, RunStats, ScoreWeights},
+    simulation::SimulationPlugin,
+};
+
+// Outcome of a single headless run.
+#[derive(Clone, Debug, Serialize)]
+pub struct RunReport {
+    pub seed: u64,
+    // Seconds of game time until the crash, or until the time limit.
+    pub survival_time: f32,
+    // None when the copter was still flying at the time limit.
+    pub crash_cause: Option<CrashCause>,
+    pub score: u32,
+    pub meters: f32,
+    pub obstacles_passed: u32,
+    pub closest_gap: Option<f32>,
+    pub impassable_obstacles: u32,
+}
+
+/*
+    A world without window, renderer, audio or input devices. Time advances
+    by exactly SIMULATION_TIMESTEP per update, so the same seed and bot
+    always play out the same run, however fast the machine steps it.
+*/
+pub fn headless_app(seed: u64, controller: Box<dyn Controller>) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .add_plugins(SimulationPlugin)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            SIMULATION_TIMESTEP,
+        )))
+        .insert_state(AppState::Playing)
+        .insert_resource(GameRng::seeded(seed))
+        .insert_resource(BotPilot(Some(controller)));
+    app
+}
+
+// Flies one run until the bot crashes or `max_time` seconds have passed.
+pub fn run_headless(
+    seed: u64,
+    controller: Box<dyn Controller>,
+    max_time: f32,
+) -> Result<RunReport, String> {
+    let mut app = headless_app(seed, controller);
+
+    let max_ticks = (max_time / SIMULATION_TIMESTEP).ceil() as u32;
+    for _ in 0..max_ticks {
+        app.update();
+        if app.world().resource::<GameState>().game_over {
+            break;
+        }
+    }
+
+    let world = app.world_mut();
+    let mut copter_query = world.query::<(&Copter, &Score)>();
+    let (copter, score) = copter_query
+        .get_single(world)
+        .map_err(|err| format!("no copter to report on for seed {seed}: {err}"))?;
+    let run_stats = world.resource::<RunStats>();
+    Ok(RunReport {
+        seed,
+        survival_time: world.resource::<Time<Virtual>>().elapsed_secs(),
+        crash_cause: copter.crash_cause,
+        score: score.total(world.resource::<ScoreWeights>()),
+        meters: score.meters(),
+        obstacles_passed: score.obstacles_passed,
+        closest_gap: run_stats.closest_gap,
+        impassable_obstacles: run_stats.impassable_obstacles,
+    })
+}
+
+// Flies a fresh bot over each of the seeds.
+pub fn run_batch(
+    seeds: impl IntoIterator<Item = u64>,
+    make_controller: impl Fn() -> Box<dyn Controller>,
+    max_time: f32,
+) -> Result<Vec<RunReport>, String> {
+    seeds
+        .into_iter()
+        .map(|seed| run_headless(seed, make_controller(), max_time))
+        .collect()
+}
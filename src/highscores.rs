@@ -0,0 +1,191 @@
+use std::{
+    cmp::Reverse,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Copter, Score},
+    enums::{AppState, GameMode},
+    events::CrashEvent,
+    levels::Level,
+    resources::{ScoreWeights, SelectedGameMode},
+    storage::{load_ron, save_ron},
+};
+
+const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HighScore {
+    pub mode: GameMode,
+    pub score: u32,
+    pub meters: u32,
+    // Seconds since the Unix epoch.
+    pub achieved_at: u64,
+    // Day of the daily challenge the run was flown on.
+    #[serde(default)]
+    pub day: Option<u64>,
+    // The first daily challenge run of its day.
+    #[serde(default)]
+    pub official: bool,
+}
+
+// Best runs of every game mode, persisted as RON in the user's data directory.
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct HighScores {
+    pub entries: Vec<HighScore>,
+}
+
+// Day of the official daily challenge run being flown, until it crashes.
+#[derive(Resource, Default)]
+pub struct OfficialAttempt(pub Option<u64>);
+
+fn high_scores_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("copter").join("highscores.ron"))
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        load_ron(high_scores_path().as_deref())
+    }
+
+    pub fn save(&self) {
+        save_ron(high_scores_path().as_deref(), self);
+    }
+
+    // Highest scores of a mode on the current leaderboard, best first.
+    pub fn top(&self, mode: GameMode) -> Vec<&HighScore> {
+        let day = mode.leaderboard_day();
+        let mut top: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.mode == mode && entry.day == day)
+            .collect();
+        top.sort_by_key(|entry| Reverse(entry.score));
+        top.truncate(MAX_HIGH_SCORES);
+        top
+    }
+
+    pub fn best(&self, mode: GameMode) -> Option<u32> {
+        self.top(mode).first().map(|entry| entry.score)
+    }
+
+    pub fn has_official(&self, day: u64) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.official && entry.day == Some(day))
+    }
+
+    // Scores the official run of the day, entered when it started.
+    fn settle_official(&mut self, day: u64, score: u32, meters: u32, achieved_at: u64) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.official && entry.day == Some(day))
+        {
+            entry.score = score;
+            entry.meters = meters;
+            entry.achieved_at = achieved_at;
+        }
+        self.entries.sort_by_key(|entry| Reverse(entry.score));
+    }
+
+    // Adds the run and forgets whatever fell out of the top of its leaderboard.
+    // Official runs are kept even when they didn't make it, to remember the day was flown.
+    pub fn insert(&mut self, entry: HighScore) {
+        let (mode, day) = (entry.mode, entry.day);
+        self.entries.push(entry);
+        self.entries.sort_by_key(|entry| Reverse(entry.score));
+
+        let mut kept = 0;
+        self.entries.retain(|entry| {
+            if entry.mode != mode || entry.day != day || entry.official {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_HIGH_SCORES
+        });
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/*
+    The first daily challenge run of the day is official from the moment it
+    starts. It goes on the leaderboard right away, without a score yet, so
+    that leaving it for the menu or closing the game still uses up the day.
+*/
+pub fn start_official_attempt(
+    selected_mode: Res<SelectedGameMode>,
+    level: Option<Res<Level>>,
+    mut official_attempt: ResMut<OfficialAttempt>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    official_attempt.0 = None;
+    if level.is_some() {
+        return;
+    }
+    let Some(day) = selected_mode.0.leaderboard_day() else {
+        return;
+    };
+    if high_scores.has_official(day) {
+        return;
+    }
+
+    official_attempt.0 = Some(day);
+    high_scores.insert(HighScore {
+        mode: selected_mode.0,
+        score: 0,
+        meters: 0,
+        achieved_at: now(),
+        day: Some(day),
+        official: true,
+    });
+    high_scores.save();
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn record_high_score(
+    mut crash_events: EventReader<CrashEvent>,
+    app_state: Res<State<AppState>>,
+    selected_mode: Res<SelectedGameMode>,
+    level: Option<Res<Level>>,
+    score_query: Query<&Score, With<Copter>>,
+    score_weights: Res<ScoreWeights>,
+    mut official_attempt: ResMut<OfficialAttempt>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    // Crashes of the attract mode demo don't count.
+    if crash_events.read().count() == 0 || *app_state.get() != AppState::Playing {
+        return;
+    }
+    // Levels are finished rather than ranked.
+    if !selected_mode.0.ranked() || level.is_some() {
+        return;
+    }
+    let Ok(score) = score_query.get_single() else {
+        return;
+    };
+
+    let (total, meters) = (score.total(&score_weights), score.meters() as u32);
+    match official_attempt.0.take() {
+        Some(day) => high_scores.settle_official(day, total, meters, now()),
+        None => high_scores.insert(HighScore {
+            mode: selected_mode.0,
+            score: total,
+            meters,
+            achieved_at: now(),
+            day: selected_mode.0.leaderboard_day(),
+            official: false,
+        }),
+    }
+    high_scores.save();
+}
@@ -0,0 +1,398 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender, TryRecvError},
+        Mutex,
+    },
+    thread,
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Copter,
+    constants::{GHOST_ALPHA, LAN_INPUT_DELAY, LAN_MAX_PLAYERS},
+    enums::{Action, Player},
+    events::NewRunEvent,
+    resources::ActionState,
+    settings::Settings,
+};
+
+/*
+    LAN races run in lockstep, spoken in JSON lines over TCP. Every machine
+    simulates the whole race itself, and only simulates a tick once it knows
+    the lift of every player for it. Lift is sent LAN_INPUT_DELAY ticks
+    ahead of the tick it is for, which hides the latency of the network.
+
+    The host relays: it collects the lift of every player, itself included,
+    and sends every complete tick back out to everyone.
+
+        host -> {"type": "start", "seed": 42, "players": 2, "player": 1}
+        host <- {"type": "lift", "tick": 4, "lift": true}
+        host -> {"type": "tick", "tick": 0, "lifts": [false, false]}
+*/
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LanMessage {
+    Start {
+        seed: u64,
+        players: usize,
+        player: usize,
+    },
+    Lift {
+        tick: u64,
+        lift: bool,
+    },
+    Tick {
+        tick: u64,
+        lifts: Vec<bool>,
+    },
+}
+
+fn send(stream: &mut TcpStream, message: &LanMessage) -> io::Result<()> {
+    serde_json::to_writer(&mut *stream, message)?;
+    stream.write_all(b"\n")
+}
+
+// None once the other end hung up.
+fn receive(reader: &mut impl BufRead) -> io::Result<Option<LanMessage>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+// Ticks sent by the host, waiting to be simulated.
+struct Inbox {
+    ticks: Receiver<Vec<bool>>,
+    ready: VecDeque<Vec<bool>>,
+    disconnected: bool,
+}
+
+impl Inbox {
+    fn receive(&mut self) {
+        loop {
+            match self.ticks.try_recv() {
+                Ok(lifts) => self.ready.push_back(lifts),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// A race over the network, from the point of view of this machine.
+#[derive(Resource)]
+pub struct LanSession {
+    pub player: Player,
+    pub players: usize,
+    pub seed: u64,
+    // Next tick to simulate.
+    tick: u64,
+    // Ticks before the world was reset for the race belong to no race.
+    racing: bool,
+    lifts: Sender<(u64, bool)>,
+    inbox: Mutex<Inbox>,
+}
+
+impl LanSession {
+    fn new(
+        player: usize,
+        players: usize,
+        seed: u64,
+        lifts: Sender<(u64, bool)>,
+        ticks: Receiver<Vec<bool>>,
+    ) -> Self {
+        // Nobody lifts during the first ticks, there was no time to send it.
+        for tick in 0..LAN_INPUT_DELAY {
+            let _ = lifts.send((tick, false));
+        }
+
+        Self {
+            player: Player(player),
+            players,
+            seed,
+            tick: 0,
+            racing: false,
+            lifts,
+            inbox: Mutex::new(Inbox {
+                ticks,
+                ready: VecDeque::new(),
+                disconnected: false,
+            }),
+        }
+    }
+
+    // Whether the lift of every player is known for the next tick.
+    pub fn ready(&self) -> bool {
+        let Ok(mut inbox) = self.inbox.lock() else {
+            return false;
+        };
+        inbox.receive();
+        self.racing && (!inbox.ready.is_empty() || inbox.disconnected)
+    }
+
+    // Lift of every player for the next tick, or None once the host is gone.
+    fn next_tick(&mut self) -> Option<Vec<bool>> {
+        let inbox = self.inbox.get_mut().ok()?;
+        inbox.receive();
+        inbox.ready.pop_front()
+    }
+}
+
+fn read_lifts(
+    mut reader: BufReader<TcpStream>,
+    player: usize,
+    lifts: Sender<(usize, Option<(u64, bool)>)>,
+) {
+    while let Ok(Some(message)) = receive(&mut reader) {
+        if let LanMessage::Lift { tick, lift } = message {
+            if lifts.send((player, Some((tick, lift)))).is_err() {
+                return;
+            }
+        }
+    }
+    eprintln!("Player {} left the race", player + 1);
+    let _ = lifts.send((player, None));
+}
+
+/*
+    Players that left never lift again, so their copters fall out of the
+    race. The host leaving ends the race for everyone.
+*/
+fn relay(
+    players: usize,
+    mut clients: Vec<TcpStream>,
+    lifts: Receiver<(usize, Option<(u64, bool)>)>,
+    host: Sender<Vec<bool>>,
+) {
+    let mut left = vec![false; players];
+    let mut pending: BTreeMap<u64, Vec<Option<bool>>> = BTreeMap::new();
+    let mut next_tick = 0;
+
+    'relay: for (player, lift) in lifts {
+        match lift {
+            Some((tick, lift)) => {
+                pending.entry(tick).or_insert_with(|| vec![None; players])[player] = Some(lift)
+            }
+            None if player == 0 => break,
+            None => left[player] = true,
+        }
+
+        while let Some(tick_lifts) = pending.get(&next_tick) {
+            let complete = tick_lifts
+                .iter()
+                .zip(&left)
+                .all(|(lift, left)| lift.is_some() || *left);
+            if !complete {
+                break;
+            }
+
+            let tick_lifts: Vec<_> = pending
+                .remove(&next_tick)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|lift| lift.unwrap_or(false))
+                .collect();
+            let message = LanMessage::Tick {
+                tick: next_tick,
+                lifts: tick_lifts.clone(),
+            };
+            for client in clients.iter_mut() {
+                let _ = send(client, &message);
+            }
+            if host.send(tick_lifts).is_err() {
+                break 'relay;
+            }
+            next_tick += 1;
+        }
+    }
+
+    // Also stops the threads still reading from the clients.
+    for client in clients {
+        let _ = client.shutdown(Shutdown::Both);
+    }
+}
+
+// Waits for everyone to join, then starts the race with the host as player one.
+pub fn host(address: &str, players: usize) -> io::Result<LanSession> {
+    if !(2..=LAN_MAX_PLAYERS).contains(&players) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("a race needs 2 to {LAN_MAX_PLAYERS} players"),
+        ));
+    }
+
+    let listener = TcpListener::bind(address)?;
+    eprintln!(
+        "Hosting a race for {players} players on {}",
+        listener.local_addr()?
+    );
+
+    let mut clients = Vec::new();
+    while clients.len() + 1 < players {
+        let (stream, peer) = listener.accept()?;
+        stream.set_nodelay(true)?;
+        eprintln!("{peer} joined as player {}", clients.len() + 2);
+        clients.push(stream);
+    }
+
+    let seed = rand::random();
+    for (index, client) in clients.iter_mut().enumerate() {
+        send(
+            client,
+            &LanMessage::Start {
+                seed,
+                players,
+                player: index + 1,
+            },
+        )?;
+    }
+
+    let (lift_sender, lift_receiver) = mpsc::channel();
+    for (index, client) in clients.iter().enumerate() {
+        let reader = BufReader::new(client.try_clone()?);
+        let lift_sender = lift_sender.clone();
+        thread::spawn(move || read_lifts(reader, index + 1, lift_sender));
+    }
+
+    let (own_sender, own_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for (tick, lift) in own_receiver {
+            if lift_sender.send((0, Some((tick, lift)))).is_err() {
+                return;
+            }
+        }
+        let _ = lift_sender.send((0, None));
+    });
+
+    let (tick_sender, tick_receiver) = mpsc::channel();
+    thread::spawn(move || relay(players, clients, lift_receiver, tick_sender));
+
+    Ok(LanSession::new(0, players, seed, own_sender, tick_receiver))
+}
+
+// Joins a race and waits for the host to start it.
+pub fn join(address: &str) -> io::Result<LanSession> {
+    let stream = TcpStream::connect(address)?;
+    stream.set_nodelay(true)?;
+    eprintln!("Joined the race on {address}, waiting for everyone else");
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let Some(LanMessage::Start {
+        seed,
+        players,
+        player,
+    }) = receive(&mut reader)?
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the host did not start the race",
+        ));
+    };
+    eprintln!("The race is on, you are player {}", player + 1);
+
+    let (own_sender, own_receiver) = mpsc::channel();
+    let mut writer = stream;
+    thread::spawn(move || {
+        for (tick, lift) in own_receiver {
+            if send(&mut writer, &LanMessage::Lift { tick, lift }).is_err() {
+                break;
+            }
+        }
+        // Leaving the race hangs up, so that the host doesn't wait for this player.
+        let _ = writer.shutdown(Shutdown::Both);
+    });
+
+    let (tick_sender, tick_receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(Some(message)) = receive(&mut reader) {
+            if let LanMessage::Tick { lifts, .. } = message {
+                if tick_sender.send(lifts).is_err() {
+                    return;
+                }
+            }
+        }
+        eprintln!("Lost the connection to the host");
+    });
+
+    Ok(LanSession::new(
+        player,
+        players,
+        seed,
+        own_sender,
+        tick_receiver,
+    ))
+}
+
+// Run condition of the simulation ticks, which wait for the lift of every player.
+pub fn lockstep_ready(lan_session: Option<Res<LanSession>>) -> bool {
+    lan_session.is_none_or(|lan_session| lan_session.ready())
+}
+
+pub fn start_lan_race(lan_session: Res<LanSession>, mut new_run_events: EventWriter<NewRunEvent>) {
+    new_run_events.send(NewRunEvent {
+        seed: lan_session.seed,
+        players: lan_session.players,
+    });
+}
+
+// Ticks are counted from the world being reset for the race.
+pub fn begin_lockstep(
+    mut new_run_events: EventReader<NewRunEvent>,
+    mut lan_session: ResMut<LanSession>,
+) {
+    if new_run_events.read().count() > 0 {
+        lan_session.racing = true;
+    }
+}
+
+// Replaces the lift of every copter with the one sent for this tick.
+pub fn lockstep_lift(
+    action_state: Res<ActionState>,
+    mut lan_session: ResMut<LanSession>,
+    mut copter_query: Query<&mut Copter>,
+) {
+    let own_lift = action_state.pressed(Action::Lift);
+    let tick_lifts = lan_session.next_tick();
+    let tick = lan_session.tick;
+    let _ = lan_session.lifts.send((tick + LAN_INPUT_DELAY, own_lift));
+    lan_session.tick += 1;
+
+    for mut copter in copter_query.iter_mut() {
+        copter.lift = match &tick_lifts {
+            Some(lifts) => lifts.get(copter.player.0).copied().unwrap_or(false),
+            // Without a host, only this gamer's copter still flies.
+            None => copter.player == lan_session.player && own_lift,
+        };
+    }
+}
+
+// Opponents are shown as ghosts, so that the gamer can tell their own copter apart.
+pub fn show_lan_opponents(
+    lan_session: Res<LanSession>,
+    settings: Res<Settings>,
+    mut copter_query: Query<(&Copter, &mut Sprite)>,
+) {
+    for (copter, mut sprite) in copter_query.iter_mut() {
+        let color = settings.display.color_theme.copter(copter.player);
+        sprite.color = if copter.player == lan_session.player {
+            color
+        } else {
+            color.with_alpha(GHOST_ALPHA)
+        };
+    }
+}
+
+// Leaving a race to the main menu leaves it for good.
+pub fn leave_lan_race(mut commands: Commands) {
+    commands.remove_resource::<LanSession>();
+}
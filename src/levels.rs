@@ -0,0 +1,334 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{CourseMarker, Obstacle},
+    constants::{
+        BORDERTILE_WIDTH, COPTER_START_X, COURSE_MARKER_ALPHA, COURSE_MARKER_WIDTH,
+        DEFAULT_BORDERTILE_HEIGHT, EDITOR_NEW_LEVEL_LENGTH, OBSTACLE_HEIGHT, OBSTACLE_SPEED,
+        OBSTACLE_WIDTH,
+    },
+    pool::ColliderPool,
+    resources::{BorderTileCurrentHeight, GameState, LevelProgress, Playfield},
+    settings::Settings,
+    terrain::Terrain,
+};
+
+/*
+    Levels:
+        Hand-authored courses, read from RON or JSON files. Everything is
+        placed by its distance along the course in pixels, counted from where
+        the copter starts, so an obstacle at a distance of 1200 reaches the
+        copter after two seconds of flight. The heights of the borders are
+        interpolated between their keyframes.
+
+        (
+            name: "First Flight",
+            borders: [
+                (distance: 0.0, top: 75.0, bottom: 75.0),
+                (distance: 3000.0, top: 200.0, bottom: 60.0),
+            ],
+            obstacles: [(distance: 1500.0, y: 0.0)],
+            checkpoints: [2500.0],
+            finish: 6000.0,
+        )
+
+        Flying past the finish line ends the run successfully.
+*/
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BorderKeyframe {
+    pub distance: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ObstaclePlacement {
+    pub distance: f32,
+    // Center of the obstacle, from the middle of the playfield.
+    pub y: f32,
+    #[serde(default = "ObstaclePlacement::default_size")]
+    pub size: Vec2,
+}
+
+impl ObstaclePlacement {
+    // Random obstacles are all of this size.
+    pub fn default_size() -> Vec2 {
+        Vec2::new(OBSTACLE_WIDTH, OBSTACLE_HEIGHT)
+    }
+}
+
+// A course flown instead of the random cave, for every run while it is loaded.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    #[serde(default)]
+    pub borders: Vec<BorderKeyframe>,
+    #[serde(default)]
+    pub obstacles: Vec<ObstaclePlacement>,
+    #[serde(default)]
+    pub checkpoints: Vec<f32>,
+    pub finish: f32,
+}
+
+impl Level {
+    // JSON files are told apart by their extension, anything else is read as RON.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let mut level: Level = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&contents).map_err(|err| err.to_string())?
+        } else {
+            ron::from_str(&contents).map_err(|err| err.to_string())?
+        };
+
+        if level.finish <= 0.0 {
+            return Err("the finish line must lie ahead of the start".to_string());
+        }
+        level.sort();
+        Ok(level)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::to_string_pretty(self).map_err(|err| err.to_string())?
+        } else {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|err| err.to_string())?
+        };
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    // An empty cave of the default height, for the editor to start from.
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            borders: vec![BorderKeyframe {
+                distance: 0.0,
+                top: DEFAULT_BORDERTILE_HEIGHT,
+                bottom: DEFAULT_BORDERTILE_HEIGHT,
+            }],
+            obstacles: Vec::new(),
+            checkpoints: Vec::new(),
+            finish: EDITOR_NEW_LEVEL_LENGTH,
+        }
+    }
+
+    // Spawning walks through everything in the order the copter meets it.
+    pub fn sort(&mut self) {
+        self.borders
+            .sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        self.obstacles
+            .sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        self.checkpoints.sort_by(f32::total_cmp);
+    }
+
+    // Progress of a run starting at a distance along the level, with everything behind it left out.
+    pub fn progress_from(&self, distance: f32) -> LevelProgress {
+        LevelProgress {
+            distance,
+            obstacles_spawned: self
+                .obstacles
+                .partition_point(|obstacle| obstacle.distance < distance),
+            checkpoints_reached: self
+                .checkpoints
+                .partition_point(|&checkpoint| checkpoint <= distance),
+            ..Default::default()
+        }
+    }
+
+    // Heights of the borders at a distance along the course.
+    pub fn borders_at(&self, distance: f32) -> BorderTileCurrentHeight {
+        let next = self
+            .borders
+            .partition_point(|keyframe| keyframe.distance <= distance);
+        let previous = next
+            .checked_sub(1)
+            .and_then(|index| self.borders.get(index));
+
+        match (previous, self.borders.get(next)) {
+            (Some(from), Some(to)) => {
+                let t = (distance - from.distance) / (to.distance - from.distance);
+                BorderTileCurrentHeight {
+                    top_border: from.top.lerp(to.top, t),
+                    bottom_border: from.bottom.lerp(to.bottom, t),
+                }
+            }
+            // Before the first keyframe and after the last one, the borders hold their height.
+            (Some(keyframe), None) | (None, Some(keyframe)) => BorderTileCurrentHeight {
+                top_border: keyframe.top,
+                bottom_border: keyframe.bottom,
+            },
+            (None, None) => BorderTileCurrentHeight::default(),
+        }
+    }
+}
+
+// Where something at a distance along the course currently is on the playfield.
+pub fn course_x(distance: f32, progress: &LevelProgress) -> f32 {
+    COPTER_START_X + distance - progress.distance
+}
+
+// Distance along the course of whatever currently is at x on the playfield.
+pub fn course_distance(x: f32, progress: &LevelProgress) -> f32 {
+    x - COPTER_START_X + progress.distance
+}
+
+pub fn spawn_level_obstacle(
+    commands: &mut Commands,
+    pool: &mut ColliderPool,
+    settings: &Settings,
+    obstacle: &ObstaclePlacement,
+    x: f32,
+) {
+    pool.spawn(
+        commands,
+        (
+            Sprite {
+                color: settings.display.color_theme.wall(),
+                custom_size: Some(obstacle.size),
+                ..Default::default()
+            },
+            Transform::from_xyz(x, obstacle.y, 1.0),
+            Obstacle,
+        ),
+    );
+}
+
+pub fn spawn_level_obstacles(
+    mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
+    level: Res<Level>,
+    playfield: Res<Playfield>,
+    settings: Res<Settings>,
+    game_state: Res<GameState>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    if game_state.game_over {
+        return;
+    }
+
+    // Obstacles appear just outside the right wall, like the random ones do.
+    while let Some(obstacle) = level.obstacles.get(progress.obstacles_spawned) {
+        let x = course_x(obstacle.distance, &progress);
+        if x - obstacle.size.x * 0.5 > playfield.half_width() {
+            break;
+        }
+
+        spawn_level_obstacle(&mut commands, &mut pool, &settings, obstacle, x);
+        progress.obstacles_spawned += 1;
+    }
+}
+
+// The border grows column by column, at the heights the level gives.
+pub fn extend_level_border(
+    level: Res<Level>,
+    playfield: Res<Playfield>,
+    mut terrain: ResMut<Terrain>,
+    game_state: Res<GameState>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    if game_state.game_over {
+        return;
+    }
+
+    // The first column follows the border lining the playfield at the start of the run.
+    let mut next = progress.next_bordertile.unwrap_or(course_distance(
+        playfield.half_width() + BORDERTILE_WIDTH * 1.5,
+        &progress,
+    ));
+    while course_x(next, &progress) <= playfield.half_width() + BORDERTILE_WIDTH {
+        terrain.push(course_x(next, &progress), &level.borders_at(next));
+        next += BORDERTILE_WIDTH;
+    }
+    progress.next_bordertile = Some(next);
+}
+
+// Runs after the world moved, so that everything spawned this tick lines up with it.
+pub fn advance_level(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    if !game_state.game_over {
+        progress.distance += OBSTACLE_SPEED * time.delta_secs();
+    }
+}
+
+// Counts the checkpoints flown past, and ends the run successfully at the finish line.
+pub fn pass_checkpoints(
+    level: Res<Level>,
+    mut game_state: ResMut<GameState>,
+    mut progress: ResMut<LevelProgress>,
+) {
+    if game_state.game_over {
+        return;
+    }
+
+    while level
+        .checkpoints
+        .get(progress.checkpoints_reached)
+        .is_some_and(|&checkpoint| checkpoint <= progress.distance)
+    {
+        progress.checkpoints_reached += 1;
+    }
+
+    if progress.distance >= level.finish {
+        progress.completed = true;
+        game_state.game_over = true;
+    }
+}
+
+fn spawn_course_markers(commands: &mut Commands, level: &Level, playfield: &Playfield) {
+    let checkpoints = level
+        .checkpoints
+        .iter()
+        .map(|&distance| (distance, Color::WHITE));
+    let finish = (level.finish, Color::srgb(1.0, 0.85, 0.3));
+
+    for (distance, color) in checkpoints.chain([finish]) {
+        commands.spawn((
+            Sprite {
+                color: color.with_alpha(COURSE_MARKER_ALPHA),
+                custom_size: Some(Vec2::new(COURSE_MARKER_WIDTH, playfield.height)),
+                ..Default::default()
+            },
+            Transform::from_xyz(COPTER_START_X + distance, 0.0, 0.5),
+            CourseMarker { distance },
+        ));
+    }
+}
+
+// Checkpoints and the finish line are drawn across the cave, but nothing collides with them.
+pub fn mark_course(
+    mut commands: Commands,
+    level: Res<Level>,
+    playfield: Res<Playfield>,
+    marker_query: Query<Entity, With<CourseMarker>>,
+) {
+    if !level.is_changed() {
+        return;
+    }
+
+    for entity in marker_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    spawn_course_markers(&mut commands, &level, &playfield);
+}
+
+pub fn place_course_markers(
+    progress: Res<LevelProgress>,
+    mut marker_query: Query<(&CourseMarker, &mut Transform)>,
+) {
+    for (marker, mut transform) in marker_query.iter_mut() {
+        transform.translation.x = course_x(marker.distance, &progress);
+    }
+}
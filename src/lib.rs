@@ -0,0 +1,26 @@
+pub mod balance;
+pub mod bot;
+pub mod broadcast;
+pub mod broadphase;
+pub mod components;
+pub mod constants;
+pub mod daily;
+pub mod editor;
+pub mod enums;
+pub mod events;
+pub mod game;
+pub mod ghosts;
+pub mod gym;
+pub mod headless;
+pub mod highscores;
+pub mod lan;
+pub mod levels;
+pub mod pool;
+pub mod practice;
+pub mod resources;
+pub mod settings;
+pub mod simulation;
+pub mod snapshot;
+pub mod storage;
+pub mod systems;
+pub mod terrain;
@@ -1,30 +1,185 @@
-use bevy::{
-    prelude::*,
-    window::{PresentMode, WindowTheme},
-};
-use constants::{WINDOW_HEIGHT, WINDOW_WIDTH};
-use game::GamePlugin;
+use std::path::{Path, PathBuf};
 
-mod components;
-mod constants;
-mod enums;
-mod game;
-mod resources;
-mod systems;
+use bevy::{prelude::*, window::WindowTheme};
+use copter::{
+    broadcast,
+    constants::{
+        BROADCAST_DEFAULT_ADDRESS, GYM_DEFAULT_ADDRESS, LAN_DEFAULT_ADDRESS, WINDOW_HEIGHT,
+        WINDOW_WIDTH,
+    },
+    editor::LevelEditor,
+    enums::{AppState, MenuScreen},
+    game::GamePlugin,
+    gym, lan,
+    levels::Level,
+    resources::CourseSeed,
+    settings::Settings,
+};
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Copter".to_string(),
-                resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
-                resizable: false,
-                present_mode: PresentMode::AutoVsync,
-                window_theme: Some(WindowTheme::Dark),
-                ..Default::default()
-            }),
+    let mut args = std::env::args().skip(1);
+    let mut course_seed = CourseSeed::default();
+    let mut lan_session = None;
+    let mut broadcast = None;
+    let mut spectator = None;
+    let mut level = None;
+    let mut editor = None;
+    match args.next().as_deref() {
+        // `--gym [address]` serves headless training environments instead of opening a window.
+        Some("--gym") => {
+            let address = args
+                .next()
+                .unwrap_or_else(|| GYM_DEFAULT_ADDRESS.to_string());
+            if let Err(error) = gym::serve(&address) {
+                eprintln!("Could not serve the gym environment on {address}: {error}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        // `--seed <number>` flies every run through the same course.
+        Some("--seed") => match args.next().map(|seed| seed.parse()) {
+            Some(Ok(seed)) => course_seed.0 = Some(seed),
+            _ => {
+                eprintln!("Expected a number after --seed");
+                std::process::exit(2);
+            }
+        },
+        // `--level <file>` flies a hand-authored course, in RON or JSON, instead of the random cave.
+        Some("--level") => {
+            let Some(path) = args.next() else {
+                eprintln!("Expected the path of a level file after --level");
+                std::process::exit(2);
+            };
+            match Level::load(Path::new(&path)) {
+                Ok(loaded) => level = Some(loaded),
+                Err(error) => {
+                    eprintln!("Could not load the level {path}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        // `--edit <file>` opens the level in the editor, or creates it there.
+        Some("--edit") => {
+            let Some(path) = args.next() else {
+                eprintln!("Expected the path of a level file after --edit");
+                std::process::exit(2);
+            };
+            match LevelEditor::open(PathBuf::from(&path)) {
+                Ok((opened, edited)) => {
+                    editor = Some(opened);
+                    level = Some(edited);
+                }
+                Err(error) => {
+                    eprintln!("Could not open the level {path}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        // `--host <players> [address]` waits for everyone to join a LAN race, then starts it.
+        Some("--host") => {
+            let Some(Ok(players)) = args.next().map(|players| players.parse()) else {
+                eprintln!("Expected the number of players after --host");
+                std::process::exit(2);
+            };
+            let address = args
+                .next()
+                .unwrap_or_else(|| LAN_DEFAULT_ADDRESS.to_string());
+            match lan::host(&address, players) {
+                Ok(session) => lan_session = Some(session),
+                Err(error) => {
+                    eprintln!("Could not host a race on {address}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        // `--join <address>` races whoever hosts there.
+        Some("--join") => {
+            let Some(address) = args.next() else {
+                eprintln!("Expected the address of the host after --join");
+                std::process::exit(2);
+            };
+            match lan::join(&address) {
+                Ok(session) => lan_session = Some(session),
+                Err(error) => {
+                    eprintln!("Could not join the race on {address}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        // `--broadcast [address]` streams the world to spectators while playing as usual.
+        Some("--broadcast") => {
+            let address = args
+                .next()
+                .unwrap_or_else(|| BROADCAST_DEFAULT_ADDRESS.to_string());
+            match broadcast::broadcast(&address) {
+                Ok(stream) => broadcast = Some(stream),
+                Err(error) => {
+                    eprintln!("Could not broadcast on {address}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        // `--spectate <address>` watches whoever broadcasts there.
+        Some("--spectate") => {
+            let Some(address) = args.next() else {
+                eprintln!("Expected the address of the broadcast after --spectate");
+                std::process::exit(2);
+            };
+            match broadcast::spectate(&address) {
+                Ok(watching) => spectator = Some(watching),
+                Err(error) => {
+                    eprintln!("Could not watch the broadcast on {address}: {error}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    // Loaded first so that the window is created in the right mode straight away.
+    let settings = Settings::load();
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: "Copter".to_string(),
+            resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+            resizable: true,
+            mode: settings.display.window_mode(),
+            present_mode: settings.display.present_mode(),
+            window_theme: Some(WindowTheme::Dark),
             ..Default::default()
-        }))
-        .add_plugins(GamePlugin)
-        .run();
+        }),
+        ..Default::default()
+    }))
+    .insert_resource(settings)
+    .insert_resource(course_seed)
+    .add_plugins(GamePlugin);
+
+    // A LAN race starts straight away, without going through the main menu.
+    if let Some(lan_session) = lan_session {
+        app.insert_resource(lan_session)
+            .insert_state(AppState::Playing)
+            .insert_state(MenuScreen::Closed);
+    }
+    if let Some(level) = level {
+        app.insert_resource(level);
+    }
+    // The editor opens straight away as well.
+    if let Some(editor) = editor {
+        app.insert_resource(editor)
+            .insert_state(AppState::Editing)
+            .insert_state(MenuScreen::Closed);
+    }
+    if let Some(broadcast) = broadcast {
+        app.insert_resource(broadcast);
+    }
+    // Spectators watch the run from its start, without going through the main menu either.
+    if let Some(spectator) = spectator {
+        app.insert_resource(spectator)
+            .insert_state(AppState::Playing)
+            .insert_state(MenuScreen::Closed);
+    }
+
+    app.run();
 }
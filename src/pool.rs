@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use crate::components::{NearMissCandidate, Obstacle, Parked, Passed};
+
+/*
+    Collider pool:
+        Obstacles come and go all the time. Rather than despawning the ones
+        that are done with, they are parked: hidden and stripped of what
+        makes them an obstacle, so that no system sees them anymore.
+        Spawning takes a parked entity when there is one, resizing and
+        moving its sprite, and only allocates a new entity when there is
+        none. Parking happens as the commands are applied, so an entity is
+        only handed out again once the commands parking it have been.
+*/
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PoolMetrics {
+    // Entities spawned because none was parked.
+    pub allocated: u64,
+    // Spawns that took a parked entity instead.
+    pub reused: u64,
+}
+
+#[derive(Resource, Default)]
+pub struct ColliderPool {
+    parked: Vec<Entity>,
+    pub metrics: PoolMetrics,
+}
+
+impl ColliderPool {
+    // Spawns an obstacle, its bundle replacing the sprite and transform of a parked entity.
+    pub fn spawn(&mut self, commands: &mut Commands, bundle: impl Bundle) -> Entity {
+        while let Some(entity) = self.parked.pop() {
+            // Parked entities despawned by someone else are skipped.
+            if let Some(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands
+                    .remove::<Parked>()
+                    .insert((bundle, Visibility::Inherited));
+                self.metrics.reused += 1;
+                return entity;
+            }
+        }
+
+        self.metrics.allocated += 1;
+        commands.spawn(bundle).id()
+    }
+
+    // Parking the same entity twice would hand it out twice, hence the marker.
+    pub fn park(&mut self, commands: &mut Commands, entity: Entity) {
+        commands
+            .entity(entity)
+            .queue(|entity: Entity, world: &mut World| {
+                let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+                    return;
+                };
+                if entity_mut.contains::<Parked>() {
+                    return;
+                }
+
+                entity_mut
+                    .remove::<(Obstacle, Passed, NearMissCandidate)>()
+                    .insert((Parked, Visibility::Hidden));
+                world.resource_mut::<ColliderPool>().parked.push(entity);
+            });
+    }
+
+    /*
+        Moves and resizes the obstacles already there to the ones given, so
+        that laying out a whole course every frame allocates nothing. Only
+        the obstacles left over are parked, and only the missing ones are
+        spawned.
+    */
+    pub fn lay_out(
+        &mut self,
+        commands: &mut Commands,
+        obstacles: &mut Query<(Entity, &mut Transform, &mut Sprite), With<Obstacle>>,
+        layout: impl IntoIterator<Item = (Vec3, Vec2)>,
+        color: Color,
+    ) {
+        let mut layout = layout.into_iter();
+        for (entity, mut transform, mut sprite) in obstacles.iter_mut() {
+            let Some((translation, size)) = layout.next() else {
+                self.park(commands, entity);
+                continue;
+            };
+            transform.translation = translation;
+            sprite.custom_size = Some(size);
+            sprite.color = color;
+            // It is a new obstacle, that no copter has come close to yet.
+            commands
+                .entity(entity)
+                .remove::<(Passed, NearMissCandidate)>();
+        }
+
+        for (translation, size) in layout {
+            self.spawn(
+                commands,
+                (
+                    Sprite {
+                        color,
+                        custom_size: Some(size),
+                        ..Default::default()
+                    },
+                    Transform::from_translation(translation),
+                    Obstacle,
+                ),
+            );
+        }
+    }
+
+    pub fn parked(&self) -> usize {
+        self.parked.len()
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    constants::{PRACTICE_CHECKPOINT_TIME, PRACTICE_MIN_FLIGHT, PRACTICE_RESPAWN_TIME},
+    enums::GameMode,
+    events::NewRunEvent,
+    resources::{GameRng, GameState, LevelProgress, SelectedGameMode},
+    snapshot::SimulationSnapshot,
+};
+
+/*
+    Practice:
+        Runs in practice mode snapshot the world every
+        PRACTICE_CHECKPOINT_TIME seconds of flight. Crashing puts the world
+        back to the latest snapshot after PRACTICE_RESPAWN_TIME, so a hard
+        part of the course can be flown again and again without flying
+        everything before it. A checkpoint crashed out of within
+        PRACTICE_MIN_FLIGHT seconds may have been taken when it was already
+        too late, so it is given up on for the one before it.
+*/
+
+#[derive(Resource)]
+pub struct Practice {
+    // Snapshots of the current run, the latest last.
+    checkpoints: Vec<SimulationSnapshot>,
+    checkpoint_timer: Timer,
+    respawn_timer: Timer,
+    // Seconds flown since the run started or was put back to a checkpoint.
+    flight_time: f32,
+}
+
+impl Default for Practice {
+    fn default() -> Self {
+        Self {
+            checkpoints: Vec::new(),
+            checkpoint_timer: Timer::new(
+                Duration::from_secs_f32(PRACTICE_CHECKPOINT_TIME),
+                TimerMode::Repeating,
+            ),
+            respawn_timer: Timer::new(
+                Duration::from_secs_f32(PRACTICE_RESPAWN_TIME),
+                TimerMode::Once,
+            ),
+            flight_time: 0.0,
+        }
+    }
+}
+
+impl Practice {
+    pub fn has_checkpoint(&self) -> bool {
+        !self.checkpoints.is_empty()
+    }
+}
+
+pub fn practicing(selected_mode: Res<SelectedGameMode>) -> bool {
+    selected_mode.0 == GameMode::Practice
+}
+
+// Runs on the fixed timestep after the world moved, so that snapshots fall between ticks.
+pub fn take_practice_checkpoint(world: &mut World) {
+    if world.resource::<GameState>().game_over {
+        return;
+    }
+
+    let delta = world.resource::<Time>().delta();
+    let mut practice = world.resource_mut::<Practice>();
+    practice.flight_time += delta.as_secs_f32();
+    if practice.checkpoint_timer.tick(delta).just_finished() {
+        let snapshot = SimulationSnapshot::take(world);
+        world.resource_mut::<Practice>().checkpoints.push(snapshot);
+    }
+}
+
+// Without a checkpoint to go back to, the course is flown from the start again.
+pub fn return_to_practice_checkpoint(world: &mut World) {
+    let finished = world.resource::<LevelProgress>().completed;
+    let game_over = world.resource::<GameState>().game_over;
+    let delta = world.resource::<Time>().delta();
+    let mut practice = world.resource_mut::<Practice>();
+
+    if !game_over || finished {
+        practice.respawn_timer.reset();
+        return;
+    }
+    if !practice.respawn_timer.tick(delta).finished() {
+        return;
+    }
+
+    if practice.flight_time < PRACTICE_MIN_FLIGHT {
+        practice.checkpoints.pop();
+    }
+    practice.respawn_timer.reset();
+    practice.checkpoint_timer.reset();
+    practice.flight_time = 0.0;
+
+    match practice.checkpoints.last().cloned() {
+        Some(snapshot) => snapshot.restore(world),
+        None => {
+            let seed = world.resource::<GameRng>().seed;
+            world.send_event(NewRunEvent { seed, players: 1 });
+        }
+    }
+}
+
+// Checkpoints belong to the run they were taken in.
+pub fn forget_practice_checkpoints(
+    mut new_run_events: EventReader<NewRunEvent>,
+    mut practice: ResMut<Practice>,
+) {
+    if new_run_events.read().count() > 0 {
+        *practice = Practice::default();
+    }
+}
@@ -1,16 +1,23 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use bevy::prelude::*;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{BORDERTILE_SPAWN_TIME, DEFAULT_BORDERTILE_HEIGHT, OBSTACLE_SPAWN_TIME},
-    enums::FluctuatingDirection,
+    constants::{
+        ATTRACT_IDLE_TIME, ATTRACT_RESTART_TIME, BORDERTILE_SPAWN_TIME, CAMERA_FOLLOW_FACTOR,
+        DEFAULT_BORDERTILE_HEIGHT, OBSTACLE_SPAWN_TIME, PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH,
+        SCORE_PER_METER, SCORE_PER_NEAR_MISS_POINT, SCORE_PER_OBSTACLE, ZOOM_PULSE_TIME,
+    },
+    daily,
+    enums::{Action, FluctuatingDirection, GameMode},
 };
 
 // Resource for tracking game state
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct GameState {
-    pub score: f32,
     pub game_over: bool,
     pub obstacle_timer: Timer,
     pub bordertile_timer: Timer,
@@ -19,7 +26,6 @@ pub struct GameState {
 impl Default for GameState {
     fn default() -> Self {
         Self {
-            score: 0.0,
             game_over: false,
             obstacle_timer: Timer::new(
                 Duration::from_secs_f32(OBSTACLE_SPAWN_TIME),
@@ -33,7 +39,52 @@ impl Default for GameState {
     }
 }
 
+// How much each source of points contributes to the total score.
+#[derive(Resource)]
+pub struct ScoreWeights {
+    pub meter: f32,
+    pub obstacle: f32,
+    pub near_miss_point: f32,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        Self {
+            meter: SCORE_PER_METER,
+            obstacle: SCORE_PER_OBSTACLE,
+            near_miss_point: SCORE_PER_NEAR_MISS_POINT,
+        }
+    }
+}
+
+// Logical area the game is played in, in world units. Extended horizontally on wide windows
+// when the playfield is set to extend instead of being letterboxed.
 #[derive(Resource)]
+pub struct Playfield {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for Playfield {
+    fn default() -> Self {
+        Self {
+            width: PLAYFIELD_WIDTH,
+            height: PLAYFIELD_HEIGHT,
+        }
+    }
+}
+
+impl Playfield {
+    pub fn half_width(&self) -> f32 {
+        self.width * 0.5
+    }
+
+    pub fn half_height(&self) -> f32 {
+        self.height * 0.5
+    }
+}
+
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct BorderTileCurrentHeight {
     pub top_border: f32,
     pub bottom_border: f32,
@@ -48,7 +99,7 @@ impl Default for BorderTileCurrentHeight {
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Clone, Serialize, Deserialize)]
 pub struct BorderTileFluctuator {
     pub direction: FluctuatingDirection,
 }
@@ -60,3 +111,221 @@ impl Default for BorderTileFluctuator {
         }
     }
 }
+
+// Toggles for the camera effects, taken from the display settings. Reduced motion overrides all of them.
+#[derive(Resource)]
+pub struct CameraEffectsSettings {
+    pub screen_shake: bool,
+    pub zoom_pulse: bool,
+    pub follow: bool,
+    pub follow_factor: f32,
+    pub reduced_motion: bool,
+}
+
+impl Default for CameraEffectsSettings {
+    fn default() -> Self {
+        Self {
+            screen_shake: true,
+            zoom_pulse: true,
+            follow: false,
+            follow_factor: CAMERA_FOLLOW_FACTOR,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl CameraEffectsSettings {
+    pub fn shake_enabled(&self) -> bool {
+        self.screen_shake && !self.reduced_motion
+    }
+
+    pub fn zoom_pulse_enabled(&self) -> bool {
+        self.zoom_pulse && !self.reduced_motion
+    }
+
+    pub fn follow_enabled(&self) -> bool {
+        self.follow && !self.reduced_motion
+    }
+}
+
+// Runtime state of the camera effects.
+#[derive(Resource)]
+pub struct CameraEffects {
+    // Between 0.0 and 1.0, the shake is proportional to its square.
+    pub trauma: f32,
+    pub zoom_pulse_timer: Timer,
+    pub follow_y: f32,
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        let mut zoom_pulse_timer =
+            Timer::new(Duration::from_secs_f32(ZOOM_PULSE_TIME), TimerMode::Once);
+        // Start finished so that no pulse plays until a near-miss happens.
+        zoom_pulse_timer.tick(Duration::from_secs_f32(ZOOM_PULSE_TIME));
+
+        Self {
+            trauma: 0.0,
+            zoom_pulse_timer,
+            follow_y: 0.0,
+        }
+    }
+}
+
+impl CameraEffects {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+// Actions held down or started this frame, gathered from every input device.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn press(&mut self, action: Action) {
+        self.pressed.insert(action);
+    }
+
+    pub fn just_press(&mut self, action: Action) {
+        self.pressed.insert(action);
+        self.just_pressed.insert(action);
+    }
+
+    pub fn any_pressed(&self) -> bool {
+        !self.pressed.is_empty()
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.pressed.remove(&action);
+        self.just_pressed.remove(&action);
+    }
+
+    pub fn clear(&mut self) {
+        self.pressed.clear();
+        self.just_pressed.clear();
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SelectedGameMode(pub GameMode);
+
+// Index of the selected item on the current menu screen.
+#[derive(Resource, Default)]
+pub struct MenuSelection(pub usize);
+
+// Action waiting for the next pressed button to be bound to it.
+#[derive(Resource, Default)]
+pub struct Rebinding {
+    pub listening: Option<Action>,
+}
+
+// Timers driving the self-playing demo.
+#[derive(Resource)]
+pub struct AttractMode {
+    // Idle time on the game over screen.
+    pub idle_timer: Timer,
+    // Time the demo lingers on its own crashes.
+    pub restart_timer: Timer,
+}
+
+impl Default for AttractMode {
+    fn default() -> Self {
+        Self {
+            idle_timer: Timer::new(Duration::from_secs_f32(ATTRACT_IDLE_TIME), TimerMode::Once),
+            restart_timer: Timer::new(
+                Duration::from_secs_f32(ATTRACT_RESTART_TIME),
+                TimerMode::Once,
+            ),
+        }
+    }
+}
+
+/*
+    Randomness of the world, reseeded at the start of every run. Only the
+    obstacles draw from it, the cave fluctuates the same way on every seed.
+    The stream is the generator behind StdRng, named directly so that its
+    state can be saved with the rest of the world.
+*/
+#[derive(Resource, Clone, Serialize, Deserialize)]
+pub struct GameRng {
+    pub seed: u64,
+    pub obstacles: ChaCha12Rng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::seeded(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn seeded(seed: u64) -> Self {
+        Self {
+            seed,
+            obstacles: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+// Seed given on the command line, so a team can race through the same course.
+#[derive(Resource, Default)]
+pub struct CourseSeed(pub Option<u64>);
+
+impl CourseSeed {
+    pub fn next(&self) -> u64 {
+        self.0.unwrap_or_else(rand::random)
+    }
+
+    // The daily course ignores the seed from the command line.
+    pub fn for_mode(&self, mode: GameMode) -> u64 {
+        match mode {
+            GameMode::Endless | GameMode::Versus | GameMode::Practice => self.next(),
+            GameMode::Daily => daily::seed_for_day(daily::today()),
+        }
+    }
+}
+
+// Facts about the current run that only balancing cares about.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct RunStats {
+    // Smallest vertical gap between the copter and anything it flew past.
+    pub closest_gap: Option<f32>,
+    // Obstacles spawned without a gap wide enough for the copter on either side.
+    pub impassable_obstacles: u32,
+}
+
+impl RunStats {
+    pub fn record_gap(&mut self, gap: f32) {
+        self.closest_gap = Some(self.closest_gap.map_or(gap, |closest| closest.min(gap)));
+    }
+}
+
+// How far the current run got through the level being flown, if any.
+#[derive(Resource, Default, Clone, Serialize, Deserialize)]
+pub struct LevelProgress {
+    // Pixels the world scrolled since the run started.
+    pub distance: f32,
+    // Obstacles of the level spawned so far, in the order of their distance.
+    pub obstacles_spawned: usize,
+    // Distance of the next sample of the border, once the first one was pushed.
+    pub next_bordertile: Option<f32>,
+    pub checkpoints_reached: usize,
+    // Whether the copter made it past the finish line.
+    pub completed: bool,
+}
+
+// Distance along the level that runs start from, so that a part of it can be flown on its own.
+#[derive(Resource, Default)]
+pub struct LevelStart(pub f32);
@@ -0,0 +1,220 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use bevy::{
+    prelude::*,
+    window::{MonitorSelection, PresentMode, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::CAMERA_FOLLOW_FACTOR,
+    enums::{Action, ColorTheme, InputBinding, PlayfieldScaling},
+    storage::{load_ron, save_ron},
+};
+
+// User settings, persisted as RON in the user's config directory.
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub display: DisplaySettings,
+    pub input: InputBindings,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub playfield_scaling: PlayfieldScaling,
+    pub reduced_motion: bool,
+    pub screen_shake: bool,
+    pub zoom_pulse: bool,
+    pub camera_follow: bool,
+    // Fraction of the copter's height the camera drifts towards when following.
+    pub follow_factor: f32,
+    pub color_theme: ColorTheme,
+    pub show_fps: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            playfield_scaling: PlayfieldScaling::default(),
+            reduced_motion: false,
+            screen_shake: true,
+            zoom_pulse: true,
+            camera_follow: false,
+            follow_factor: CAMERA_FOLLOW_FACTOR,
+            color_theme: ColorTheme::default(),
+            show_fps: false,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 0.8,
+            music_volume: 0.5,
+            sfx_volume: 0.8,
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn music(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    pub fn sfx(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct InputBindings {
+    pub actions: HashMap<Action, Vec<InputBinding>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| (action, Self::default_bindings(action)))
+                .collect(),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn default_bindings(action: Action) -> Vec<InputBinding> {
+        match action {
+            Action::Lift => vec![
+                InputBinding::Key(KeyCode::Space),
+                InputBinding::Key(KeyCode::ArrowUp),
+                InputBinding::Mouse(MouseButton::Left),
+                InputBinding::Gamepad(GamepadButton::South),
+                InputBinding::Gamepad(GamepadButton::RightTrigger2),
+            ],
+            Action::LiftPlayerTwo => vec![
+                InputBinding::Key(KeyCode::KeyW),
+                InputBinding::Key(KeyCode::ShiftLeft),
+                InputBinding::Gamepad(GamepadButton::South),
+                InputBinding::Gamepad(GamepadButton::RightTrigger2),
+            ],
+            Action::Pause => vec![
+                InputBinding::Key(KeyCode::Escape),
+                InputBinding::Key(KeyCode::KeyP),
+                InputBinding::Gamepad(GamepadButton::Start),
+            ],
+            Action::Restart => vec![
+                InputBinding::Key(KeyCode::KeyR),
+                InputBinding::Gamepad(GamepadButton::West),
+            ],
+            Action::Confirm => vec![
+                InputBinding::Key(KeyCode::Enter),
+                InputBinding::Gamepad(GamepadButton::South),
+            ],
+            Action::Back => vec![
+                InputBinding::Key(KeyCode::Escape),
+                InputBinding::Key(KeyCode::Backspace),
+                InputBinding::Gamepad(GamepadButton::East),
+            ],
+            Action::ToggleFullscreen => vec![InputBinding::Key(KeyCode::F11)],
+            Action::QuickSave => vec![InputBinding::Key(KeyCode::F5)],
+            Action::QuickLoad => vec![InputBinding::Key(KeyCode::F9)],
+            Action::PlaceBorder => vec![InputBinding::Key(KeyCode::KeyB)],
+            Action::PlaceObstacle => vec![InputBinding::Key(KeyCode::KeyO)],
+            Action::PlaceCheckpoint => vec![InputBinding::Key(KeyCode::KeyC)],
+            Action::PlaceFinish => vec![InputBinding::Key(KeyCode::KeyF)],
+            Action::ScrollLeft => vec![InputBinding::Key(KeyCode::ArrowLeft)],
+            Action::ScrollRight => vec![InputBinding::Key(KeyCode::ArrowRight)],
+            Action::TestFly => vec![InputBinding::Key(KeyCode::KeyT)],
+            Action::SaveLevel => vec![InputBinding::Key(KeyCode::KeyS)],
+        }
+    }
+
+    pub fn get(&self, action: Action) -> &[InputBinding] {
+        self.actions.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn add(&mut self, action: Action, binding: InputBinding) {
+        let bindings = self.actions.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn reset(&mut self, action: Action) {
+        self.actions.insert(action, Self::default_bindings(action));
+    }
+}
+
+// The first binding of an action, to name it in the help.
+pub fn binding_hint(settings: &Settings, action: Action) -> String {
+    settings
+        .input
+        .get(action)
+        .first()
+        .map_or_else(|| "(unbound)".to_string(), ToString::to_string)
+}
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("copter").join("settings.ron"))
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings: Self = load_ron(settings_path().as_deref());
+        // Actions added since the settings were saved get their default bindings.
+        for action in Action::ALL {
+            settings
+                .input
+                .actions
+                .entry(action)
+                .or_insert_with(|| InputBindings::default_bindings(action));
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        save_ron(settings_path().as_deref(), self);
+    }
+}
+
+pub fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
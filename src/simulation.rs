@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    bot::{fly_bot, BotPilot},
+    broadphase::{update_broad_phase, BroadPhase},
+    components::{Copter, NearMissCombo, Obstacle, Score},
+    constants::{COPTER_SIZE, COPTER_START_X, SIMULATION_TIMESTEP, VERSUS_START_SPREAD},
+    enums::{Action, AppState, Player},
+    events::{CrashEvent, NearMissEvent, NewRunEvent},
+    levels::{
+        advance_level, extend_level_border, mark_course, pass_checkpoints, place_course_markers,
+        spawn_level_obstacles, Level,
+    },
+    pool::ColliderPool,
+    resources::{
+        ActionState, BorderTileCurrentHeight, BorderTileFluctuator, CourseSeed, GameRng, GameState,
+        LevelProgress, LevelStart, Playfield, RunStats, ScoreWeights, SelectedGameMode,
+    },
+    settings::Settings,
+    systems::{
+        bordertiles::{border_movement, extend_border, spawn_full_border, spawn_init_border},
+        collision::{collision_detection, near_miss_detection},
+        copter::{copter_movement, gamer_lift},
+        obstacles::{obstacle_movement, spawn_obstacles},
+        ui::update_score,
+    },
+    terrain::Terrain,
+};
+
+/*
+    The game logic on its own: the copter, the world scrolling past it,
+    collisions and scoring. It needs no window, renderer, audio or input
+    devices, so bots can fly it headless. GamePlugin adds everything the
+    gamer sees and hears on top of it.
+*/
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        // Normally loaded in main, before the window is created.
+        app.init_resource::<Settings>()
+            .insert_resource(GameState::default())
+            .insert_resource(Playfield::default())
+            .insert_resource(ScoreWeights::default())
+            .insert_resource(BorderTileCurrentHeight::default())
+            .insert_resource(BorderTileFluctuator::default())
+            .insert_resource(ActionState::default())
+            .insert_resource(RunStats::default())
+            .insert_resource(LevelProgress::default())
+            .insert_resource(LevelStart::default())
+            .insert_resource(GameRng::default())
+            // Set from the command line in main.
+            .init_resource::<CourseSeed>()
+            .init_resource::<SelectedGameMode>()
+            .insert_resource(BotPilot::default())
+            .insert_resource(ColliderPool::default())
+            .insert_resource(Terrain::default())
+            .insert_resource(BroadPhase::default())
+            .init_state::<AppState>()
+            .add_event::<CrashEvent>()
+            .add_event::<NearMissEvent>()
+            .add_event::<NewRunEvent>()
+            .insert_resource(Time::<Fixed>::from_duration(Duration::from_secs_f32(
+                SIMULATION_TIMESTEP,
+            )))
+            .add_systems(Startup, spawn_copter)
+            // Once the playfield has been fitted to the window, so that the walls reach both edges.
+            .add_systems(PostStartup, spawn_init_border)
+            .add_systems(
+                Update,
+                (restart.in_set(SimulationSet::Restart), reset_run).chain(),
+            )
+            .add_systems(
+                Update,
+                (mark_course, place_course_markers)
+                    .chain()
+                    .run_if(resource_exists::<Level>),
+            )
+            /*
+                The world only moves on the fixed timestep, and always in the same
+                order, so that a seed and the lift of every tick make the same run
+                at any frame rate and on every machine. The demo behind the main
+                menu flies too, but doesn't score. A loaded level replaces the
+                random cave.
+            */
+            .add_systems(
+                FixedUpdate,
+                (
+                    spawn_obstacles.run_if(not(resource_exists::<Level>)),
+                    spawn_level_obstacles.run_if(resource_exists::<Level>),
+                    obstacle_movement,
+                    extend_border.run_if(not(resource_exists::<Level>)),
+                    extend_level_border.run_if(resource_exists::<Level>),
+                    border_movement,
+                    advance_level.run_if(resource_exists::<Level>),
+                    fly_bot,
+                    gamer_lift,
+                    copter_movement,
+                    update_broad_phase,
+                    collision_detection,
+                    pass_checkpoints.run_if(resource_exists::<Level>),
+                    (near_miss_detection, update_score).run_if(in_state(AppState::Playing)),
+                )
+                    .chain()
+                    .in_set(SimulationSet::Step),
+            );
+    }
+}
+
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SimulationSet {
+    // A single tick of the world.
+    Step,
+    // Restarts asked for by the gamer.
+    Restart,
+}
+
+// With several players, copters start spread apart so that each gamer can tell theirs apart.
+fn start_position(player: Player, players: usize) -> Vec3 {
+    let y = if players < 2 {
+        0.0
+    } else {
+        VERSUS_START_SPREAD * (1.0 - 2.0 * player.0 as f32 / (players - 1) as f32)
+    };
+    Vec3::new(COPTER_START_X, y, 1.0)
+}
+
+pub fn copter_bundle(player: Player, players: usize, settings: &Settings) -> impl Bundle {
+    (
+        Sprite {
+            color: settings.display.color_theme.copter(player),
+            custom_size: Some(COPTER_SIZE),
+            ..Default::default()
+        },
+        Transform::from_translation(start_position(player, players)),
+        Copter {
+            velocity: 0.0,
+            player,
+            lift: false,
+            crash_cause: None,
+        },
+        Score::default(),
+        NearMissCombo::default(),
+    )
+}
+
+fn spawn_copter(mut commands: Commands, settings: Res<Settings>) {
+    commands.spawn(copter_bundle(Player::ONE, 1, &settings));
+}
+
+// Restarting flies a fresh course with the same copters, unless the seed was fixed.
+fn restart(
+    action_state: Res<ActionState>,
+    game_state: Res<GameState>,
+    course_seed: Res<CourseSeed>,
+    selected_mode: Res<SelectedGameMode>,
+    copter_query: Query<&Copter>,
+    mut new_run_events: EventWriter<NewRunEvent>,
+) {
+    if game_state.game_over && action_state.just_pressed(Action::Restart) {
+        new_run_events.send(NewRunEvent {
+            seed: course_seed.for_mode(selected_mode.0),
+            players: copter_query.iter().count(),
+        });
+    }
+}
+
+/*
+    Everything the course is generated from is put back the way it was at
+    startup, so the same seed always flies through the same course.
+*/
+#[allow(clippy::too_many_arguments)]
+fn reset_run(
+    mut new_run_events: EventReader<NewRunEvent>,
+    mut game_state: ResMut<GameState>,
+    mut run_stats: ResMut<RunStats>,
+    mut level_progress: ResMut<LevelProgress>,
+    mut rng: ResMut<GameRng>,
+    mut bordertile_cur_height: ResMut<BorderTileCurrentHeight>,
+    mut bordertile_fluctuator: ResMut<BorderTileFluctuator>,
+    playfield: Res<Playfield>,
+    settings: Res<Settings>,
+    level: Option<Res<Level>>,
+    level_start: Res<LevelStart>,
+    mut terrain: ResMut<Terrain>,
+    mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
+    obstacle_query: Query<Entity, With<Obstacle>>,
+    copter_query: Query<Entity, With<Copter>>,
+) {
+    let Some(event) = new_run_events.read().last() else {
+        return;
+    };
+
+    // Also resets the obstacle timer, important to respawn obstacles once gamer restarts.
+    *game_state = GameState::default();
+    *run_stats = RunStats::default();
+    *level_progress = level
+        .as_deref()
+        .map_or_else(LevelProgress::default, |level| {
+            level.progress_from(level_start.0)
+        });
+    *rng = GameRng::seeded(event.seed);
+    *bordertile_cur_height = BorderTileCurrentHeight::default();
+    *bordertile_fluctuator = BorderTileFluctuator::default();
+
+    for entity in obstacle_query.iter() {
+        pool.park(&mut commands, entity);
+    }
+    spawn_full_border(&mut terrain, &playfield, level.as_deref(), &level_progress);
+
+    // Copters are respawned rather than reset, as the number of players may have changed.
+    for entity in copter_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for player in 0..event.players.max(1) {
+        commands.spawn(copter_bundle(Player(player), event.players, &settings));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::{Copter, NearMissCombo, Obstacle, Passed, Score},
+    enums::Action,
+    pool::ColliderPool,
+    practice::Practice,
+    resources::{
+        ActionState, BorderTileCurrentHeight, BorderTileFluctuator, GameRng, GameState,
+        LevelProgress, RunStats,
+    },
+    settings::Settings,
+    simulation::copter_bundle,
+    terrain::Terrain,
+};
+
+/*
+    Snapshots:
+        Everything the simulation needs to carry on from a moment of a run:
+        the resources the course is generated from, the timers and the RNG
+        streams, every copter and obstacle, and the walls. Restoring one
+        replaces the world as it is, parking the obstacles in the collider
+        pool and spawning the saved ones from it, so the run continues
+        exactly as it did after the snapshot was taken. Near-miss candidates
+        point at copters that are respawned on restore, so they are left
+        out.
+
+        Snapshots are saved as RON, so that a tricky moment of a run can be
+        passed around, tweaked by hand and loaded again, by gamers as well
+        as by tests. Any run can be quick-saved and quick-loaded, except for
+        LAN races and spectating, where the world isn't this instance's
+        alone.
+*/
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CopterState {
+    pub copter: Copter,
+    pub translation: Vec3,
+    pub score: Score,
+    pub combo: NearMissCombo,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ObstacleState {
+    pub translation: Vec3,
+    pub size: Vec2,
+    pub passed: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SimulationSnapshot {
+    pub game_state: GameState,
+    pub run_stats: RunStats,
+    pub level_progress: LevelProgress,
+    pub rng: GameRng,
+    pub bordertile_cur_height: BorderTileCurrentHeight,
+    pub bordertile_fluctuator: BorderTileFluctuator,
+    pub copters: Vec<CopterState>,
+    pub obstacles: Vec<ObstacleState>,
+    pub terrain: Terrain,
+}
+
+impl SimulationSnapshot {
+    pub fn take(world: &mut World) -> Self {
+        let mut copters: Vec<_> = world
+            .query::<(&Copter, &Transform, &Score, &NearMissCombo)>()
+            .iter(world)
+            .map(|(copter, transform, score, combo)| CopterState {
+                copter: copter.clone(),
+                translation: transform.translation,
+                score: score.clone(),
+                combo: combo.clone(),
+            })
+            .collect();
+        copters.sort_by_key(|state| state.copter.player);
+
+        let obstacles = world
+            .query_filtered::<(&Transform, &Sprite, Has<Passed>), With<Obstacle>>()
+            .iter(world)
+            .map(obstacle_state)
+            .collect();
+
+        Self {
+            game_state: world.resource::<GameState>().clone(),
+            run_stats: world.resource::<RunStats>().clone(),
+            level_progress: world.resource::<LevelProgress>().clone(),
+            rng: world.resource::<GameRng>().clone(),
+            bordertile_cur_height: world.resource::<BorderTileCurrentHeight>().clone(),
+            bordertile_fluctuator: world.resource::<BorderTileFluctuator>().clone(),
+            copters,
+            obstacles,
+            terrain: world.resource::<Terrain>().clone(),
+        }
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        *world.resource_mut::<GameState>() = self.game_state.clone();
+        *world.resource_mut::<RunStats>() = self.run_stats.clone();
+        *world.resource_mut::<LevelProgress>() = self.level_progress.clone();
+        *world.resource_mut::<GameRng>() = self.rng.clone();
+        *world.resource_mut::<BorderTileCurrentHeight>() = self.bordertile_cur_height.clone();
+        *world.resource_mut::<BorderTileFluctuator>() = self.bordertile_fluctuator.clone();
+        *world.resource_mut::<Terrain>() = self.terrain.clone();
+
+        let copters: Vec<_> = world
+            .query_filtered::<Entity, With<Copter>>()
+            .iter(world)
+            .collect();
+        for entity in copters {
+            world.despawn(entity);
+        }
+        let obstacles: Vec<_> = world
+            .query_filtered::<Entity, With<Obstacle>>()
+            .iter(world)
+            .collect();
+        world.resource_scope(|world, mut pool: Mut<ColliderPool>| {
+            let mut commands = world.commands();
+            for entity in obstacles {
+                pool.park(&mut commands, entity);
+            }
+        });
+        // Parked now, so that the obstacles below take their entities.
+        world.flush();
+
+        world.resource_scope(|world, settings: Mut<Settings>| {
+            let wall = settings.display.color_theme.wall();
+
+            for state in &self.copters {
+                let players = self.copters.len();
+                world
+                    .spawn(copter_bundle(state.copter.player, players, &settings))
+                    .insert((
+                        Transform::from_translation(state.translation),
+                        state.copter.clone(),
+                        state.score.clone(),
+                        state.combo.clone(),
+                    ));
+            }
+            world.resource_scope(|world, mut pool: Mut<ColliderPool>| {
+                let mut commands = world.commands();
+                for state in &self.obstacles {
+                    let obstacle =
+                        pool.spawn(&mut commands, (obstacle_bundle(state, wall), Obstacle));
+                    if state.passed {
+                        commands.entity(obstacle).insert(Passed);
+                    }
+                }
+            });
+        });
+        world.flush();
+    }
+}
+
+fn obstacle_state((transform, sprite, passed): (&Transform, &Sprite, bool)) -> ObstacleState {
+    ObstacleState {
+        translation: transform.translation,
+        size: sprite.custom_size.unwrap_or(Vec2::ONE),
+        passed,
+    }
+}
+
+fn obstacle_bundle(state: &ObstacleState, color: Color) -> (Sprite, Transform) {
+    (
+        Sprite {
+            color,
+            custom_size: Some(state.size),
+            ..Default::default()
+        },
+        Transform::from_translation(state.translation),
+    )
+}
+
+pub fn save_snapshot(world: &mut World, path: &Path) -> Result<(), String> {
+    let snapshot = SimulationSnapshot::take(world);
+    let contents = ron::ser::to_string_pretty(&snapshot, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(path, contents).map_err(|err| err.to_string())
+}
+
+pub fn load_snapshot(world: &mut World, path: &Path) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let snapshot: SimulationSnapshot = ron::from_str(&contents).map_err(|err| err.to_string())?;
+    snapshot.restore(world);
+    Ok(())
+}
+
+fn quicksave_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("copter").join("quicksave.ron"))
+}
+
+pub fn quick_save(world: &mut World) {
+    if !world
+        .resource::<ActionState>()
+        .just_pressed(Action::QuickSave)
+    {
+        return;
+    }
+    let Some(path) = quicksave_path() else {
+        warn!("No user directory found, nothing will be saved.");
+        return;
+    };
+
+    match save_snapshot(world, &path) {
+        Ok(()) => info!("Saved the run to {}", path.display()),
+        Err(err) => warn!("Failed to save {}: {err}", path.display()),
+    }
+}
+
+// The checkpoints taken so far may belong to another run than the one loaded.
+pub fn quick_load(world: &mut World) {
+    if !world
+        .resource::<ActionState>()
+        .just_pressed(Action::QuickLoad)
+    {
+        return;
+    }
+    let Some(path) = quicksave_path() else {
+        return;
+    };
+
+    match load_snapshot(world, &path) {
+        Ok(()) => *world.resource_mut::<Practice>() = Practice::default(),
+        Err(err) => warn!("Failed to load {}: {err}", path.display()),
+    }
+}
@@ -0,0 +1,39 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+// Missing or unreadable files fall back to the defaults instead of stopping the game.
+pub fn load_ron<T: DeserializeOwned + Default>(path: Option<&Path>) -> T {
+    let Some(path) = path else {
+        return T::default();
+    };
+    let Ok(contents) = fs::read_to_string(path) else {
+        return T::default();
+    };
+
+    ron::from_str(&contents).unwrap_or_else(|err| {
+        warn!("Ignoring malformed file {}: {err}", path.display());
+        T::default()
+    })
+}
+
+pub fn save_ron<T: Serialize>(path: Option<&Path>, value: &T) {
+    let Some(path) = path else {
+        warn!("No user directory found, nothing will be saved.");
+        return;
+    };
+
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+            }
+            fs::write(path, contents).map_err(|err| err.to_string())
+        });
+
+    if let Err(err) = result {
+        warn!("Failed to save {}: {err}", path.display());
+    }
+}
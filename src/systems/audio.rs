@@ -0,0 +1,255 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{
+    audio::{Source, Volume},
+    prelude::*,
+};
+
+use crate::{
+    components::{Copter, MusicTrack, RotorSound},
+    constants::{MAX_COPTER_VELOCITY, MUSIC_CROSSFADE_TIME, ROTOR_PITCH_RANGE, SAMPLE_RATE},
+    enums::{AppState, Music},
+    events::{CrashEvent, NearMissEvent},
+    resources::GameState,
+    settings::Settings,
+};
+
+/*
+    All sounds are synthesized on the fly instead of being loaded from files,
+    so the game ships without any audio assets. Each variant is a short clip;
+    the looping ones are exactly a whole number of cycles long so that they
+    repeat without clicks.
+*/
+#[derive(Asset, TypePath, Clone, Copy)]
+pub enum Synth {
+    Rotor,
+    Crash,
+    Chime,
+    MenuMusic,
+    GameplayMusic,
+}
+
+const MENU_MELODY: [f32; 8] = [220.0, 261.63, 329.63, 261.63, 196.0, 246.94, 293.66, 246.94];
+const MENU_NOTE_TIME: f32 = 0.5;
+const GAMEPLAY_MELODY: [f32; 8] = [220.0, 329.63, 440.0, 329.63, 246.94, 369.99, 493.88, 369.99];
+const GAMEPLAY_NOTE_TIME: f32 = 0.25;
+
+impl Synth {
+    fn duration(&self) -> f32 {
+        match self {
+            Synth::Rotor => 0.25,
+            Synth::Crash => 0.7,
+            Synth::Chime => 0.4,
+            Synth::MenuMusic => MENU_NOTE_TIME * MENU_MELODY.len() as f32,
+            Synth::GameplayMusic => GAMEPLAY_NOTE_TIME * GAMEPLAY_MELODY.len() as f32,
+        }
+    }
+}
+
+impl Decodable for Synth {
+    type DecoderItem = f32;
+    type Decoder = SynthDecoder;
+
+    fn decoder(&self) -> Self::Decoder {
+        SynthDecoder {
+            synth: *self,
+            sample: 0,
+            total_samples: (self.duration() * SAMPLE_RATE as f32) as u32,
+            noise: 0x2545_f491,
+        }
+    }
+}
+
+pub struct SynthDecoder {
+    synth: Synth,
+    sample: u32,
+    total_samples: u32,
+    noise: u32,
+}
+
+fn saw(phase: f32) -> f32 {
+    2.0 * phase.fract() - 1.0
+}
+
+fn triangle(phase: f32) -> f32 {
+    1.0 - 4.0 * (phase.fract() - 0.5).abs()
+}
+
+fn melody(notes: &[f32], note_time: f32, t: f32) -> f32 {
+    let note = (t / note_time) as usize % notes.len();
+    let note_t = t % note_time;
+    let envelope = (-4.0 * note_t).exp() * (note_t * 200.0).min(1.0);
+    // Bass an octave below the first note of each half of the melody.
+    let bass = notes[note / 4 * 4] * 0.5;
+
+    0.3 * envelope * triangle(notes[note] * t) + 0.15 * triangle(bass * t)
+}
+
+impl SynthDecoder {
+    // Xorshift, good enough for noise and much cheaper than a proper RNG.
+    fn white_noise(&mut self) -> f32 {
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Iterator for SynthDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sample >= self.total_samples {
+            return None;
+        }
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        self.sample += 1;
+
+        let value = match self.synth {
+            // Low buzz chopped by the blades 16 times a second.
+            Synth::Rotor => {
+                let chop = (0.5 + 0.5 * (TAU * 16.0 * t).sin()).powi(3);
+                0.35 * saw(56.0 * t) * (0.3 + 0.7 * chop)
+            }
+            // Burst of noise over a low thump.
+            Synth::Crash => {
+                let envelope = (-6.0 * t).exp();
+                envelope * (0.5 * self.white_noise() + 0.5 * (TAU * 60.0 * t).sin())
+            }
+            Synth::Chime => {
+                let envelope = (-8.0 * t).exp();
+                0.3 * envelope * ((TAU * 880.0 * t).sin() + 0.5 * (TAU * 1320.0 * t).sin())
+            }
+            Synth::MenuMusic => melody(&MENU_MELODY, MENU_NOTE_TIME, t),
+            Synth::GameplayMusic => melody(&GAMEPLAY_MELODY, GAMEPLAY_NOTE_TIME, t),
+        };
+
+        Some(value)
+    }
+}
+
+impl Source for SynthDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.synth.duration()))
+    }
+}
+
+// Sinks are only created when an audio device is present, so every system below
+// has to cope with them missing.
+pub fn setup_audio(mut commands: Commands, mut synths: ResMut<Assets<Synth>>) {
+    commands.spawn((
+        AudioPlayer(synths.add(Synth::Rotor)),
+        PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+        RotorSound,
+    ));
+
+    for (music, synth) in [
+        (Music::Menu, Synth::MenuMusic),
+        (Music::Gameplay, Synth::GameplayMusic),
+    ] {
+        commands.spawn((
+            AudioPlayer(synths.add(synth)),
+            PlaybackSettings::LOOP.with_volume(Volume::new(0.0)),
+            MusicTrack { music, fade: 0.0 },
+        ));
+    }
+}
+
+// The rotor spins faster while climbing and slower while falling.
+pub fn rotor_sound(
+    time: Res<Time<Virtual>>,
+    app_state: Res<State<AppState>>,
+    game_state: Res<GameState>,
+    settings: Res<Settings>,
+    copter_query: Query<&Copter>,
+    rotor_query: Query<&AudioSink, With<RotorSound>>,
+) {
+    let Ok(rotor) = rotor_query.get_single() else {
+        return;
+    };
+
+    let flying =
+        *app_state.get() == AppState::Playing && !game_state.game_over && !time.is_paused();
+
+    // With several copters, the rotor follows the fastest climbing one still flying.
+    let copter = copter_query
+        .iter()
+        .filter(|copter| !copter.crashed())
+        .max_by(|a, b| a.velocity.total_cmp(&b.velocity));
+
+    match copter {
+        Some(copter) if flying => {
+            let velocity = copter.velocity / MAX_COPTER_VELOCITY;
+            rotor.set_speed(1.0 + velocity * ROTOR_PITCH_RANGE);
+            rotor.set_volume(settings.audio.sfx());
+        }
+        _ => rotor.set_volume(0.0),
+    }
+}
+
+pub fn play_sound_effects(
+    mut commands: Commands,
+    mut crash_events: EventReader<CrashEvent>,
+    mut near_miss_events: EventReader<NearMissEvent>,
+    app_state: Res<State<AppState>>,
+    settings: Res<Settings>,
+    mut synths: ResMut<Assets<Synth>>,
+) {
+    let crashes = crash_events.read().count();
+    let near_misses = near_miss_events.read().count();
+
+    // The demo behind the main menu flies silently.
+    if *app_state.get() != AppState::Playing {
+        return;
+    }
+
+    for (synth, count) in [(Synth::Crash, crashes), (Synth::Chime, near_misses)] {
+        // Sounds triggered in the same frame would just play on top of each other.
+        if count > 0 {
+            commands.spawn((
+                AudioPlayer(synths.add(synth)),
+                PlaybackSettings::DESPAWN.with_volume(Volume::new(settings.audio.sfx())),
+            ));
+        }
+    }
+}
+
+// Fade the track that fits the current screen in and every other track out.
+pub fn crossfade_music(
+    time: Res<Time<Real>>,
+    app_state: Res<State<AppState>>,
+    game_state: Res<GameState>,
+    settings: Res<Settings>,
+    mut music_query: Query<(&mut MusicTrack, Option<&AudioSink>)>,
+) {
+    let current = if *app_state.get() == AppState::MainMenu || game_state.game_over {
+        Music::Menu
+    } else {
+        Music::Gameplay
+    };
+    let step = time.delta_secs() / MUSIC_CROSSFADE_TIME;
+
+    for (mut track, sink) in music_query.iter_mut() {
+        track.fade = if track.music == current {
+            (track.fade + step).min(1.0)
+        } else {
+            (track.fade - step).max(0.0)
+        };
+
+        if let Some(sink) = sink {
+            sink.set_volume(track.fade * settings.audio.music());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::{
+    bot::{BotPilot, Controller, Observation},
+    constants::{AUTOPILOT_LOOKAHEAD, AUTOPILOT_SAFETY_MARGIN, COPTER_SIZE},
+    editor::LevelEditor,
+    enums::{AppState, MenuScreen},
+    events::NewRunEvent,
+    resources::{ActionState, AttractMode, GameState},
+    systems::main_menu::leave_run,
+};
+
+/*
+    Autopilot:
+        Aim for the middle of the closer gap around the next obstacle that is
+        not yet behind the copter, bounded by the walls on the way there.
+        Without an obstacle ahead, hold the middle of the tunnel.
+        The copter's y is predicted AUTOPILOT_LOOKAHEAD seconds ahead and
+        lift is held whenever that prediction falls below the target.
+*/
+
+// Lowest and highest y free of the walls' samples within an x-axis range.
+fn tunnel(x_range: (f32, f32), observation: &Observation) -> (f32, f32) {
+    let half_height = observation.playfield_size.y * 0.5;
+    let mut bottom = -half_height;
+    let mut top = half_height;
+
+    for wall in &observation.walls {
+        if wall.x < x_range.0 || wall.x > x_range.1 {
+            continue;
+        }
+        if wall.top {
+            top = top.min(half_height - wall.height);
+        } else {
+            bottom = bottom.max(-half_height + wall.height);
+        }
+    }
+
+    (bottom, top)
+}
+
+fn target_y(observation: &Observation) -> f32 {
+    let copter_pos = observation.copter_position;
+    let copter_left = copter_pos.x - COPTER_SIZE.x * 0.5;
+
+    let target = match observation.obstacles.first() {
+        Some(obstacle) => {
+            let (position, size) = (obstacle.position, obstacle.size);
+            let (bottom, top) = tunnel(
+                (copter_left, position.x + size.x * 0.5 + COPTER_SIZE.x),
+                observation,
+            );
+            let obstacle_bottom = position.y - size.y * 0.5;
+            let obstacle_top = position.y + size.y * 0.5;
+            let above = (obstacle_top + top) * 0.5;
+            let below = (bottom + obstacle_bottom) * 0.5;
+            let min_gap = COPTER_SIZE.y + AUTOPILOT_SAFETY_MARGIN * 2.0;
+
+            // Prefer the closer gap, unless it's too narrow to fit through.
+            let closer_is_above = (above - copter_pos.y).abs() < (below - copter_pos.y).abs();
+            match (
+                top - obstacle_top >= min_gap,
+                obstacle_bottom - bottom >= min_gap,
+            ) {
+                (true, true) if closer_is_above => above,
+                (true, true) => below,
+                (true, false) => above,
+                (false, true) => below,
+                (false, false) if top - obstacle_top > obstacle_bottom - bottom => above,
+                (false, false) => below,
+            }
+        }
+        None => {
+            let (bottom, top) = tunnel(
+                (copter_left, copter_pos.x + COPTER_SIZE.x * 4.0),
+                observation,
+            );
+            (bottom + top) * 0.5
+        }
+    };
+
+    // Never aim into the walls right around the copter.
+    let (bottom, top) = tunnel(
+        (
+            copter_left - COPTER_SIZE.x,
+            copter_pos.x + COPTER_SIZE.x * 1.5,
+        ),
+        observation,
+    );
+    let clearance = COPTER_SIZE.y * 0.5 + AUTOPILOT_SAFETY_MARGIN;
+    if bottom + clearance < top - clearance {
+        target.clamp(bottom + clearance, top - clearance)
+    } else {
+        (bottom + top) * 0.5
+    }
+}
+
+// Heuristic pilot for the demo behind the main menu and a baseline for balancing.
+#[derive(Default)]
+pub struct Autopilot;
+
+impl Controller for Autopilot {
+    fn decide(&mut self, observation: &Observation) -> bool {
+        let predicted_y =
+            observation.copter_position.y + observation.copter_velocity * AUTOPILOT_LOOKAHEAD;
+        predicted_y < target_y(observation)
+    }
+}
+
+// The autopilot flies the demo behind the main menu, the gamer flies the runs.
+pub fn hand_over_to_autopilot(mut bot_pilot: ResMut<BotPilot>) {
+    bot_pilot.0 = Some(Box::new(Autopilot));
+}
+
+pub fn hand_over_to_gamer(mut bot_pilot: ResMut<BotPilot>) {
+    bot_pilot.0 = None;
+}
+
+/*
+    The demo restarts itself shortly after crashing. A gamer that leaves
+    the game over screen alone for ATTRACT_IDLE_TIME is sent back to the
+    main menu, where the demo takes over.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn attract_mode(
+    time: Res<Time>,
+    app_state: Res<State<AppState>>,
+    menu_screen: Res<State<MenuScreen>>,
+    action_state: Res<ActionState>,
+    game_state: Res<GameState>,
+    editor: Option<Res<LevelEditor>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut new_run_events: EventWriter<NewRunEvent>,
+) {
+    if !game_state.game_over {
+        attract_mode.idle_timer.reset();
+        attract_mode.restart_timer.reset();
+        return;
+    }
+
+    match app_state.get() {
+        AppState::MainMenu => {
+            if attract_mode.restart_timer.tick(time.delta()).finished() {
+                attract_mode.restart_timer.reset();
+                new_run_events.send(NewRunEvent {
+                    seed: rand::random(),
+                    players: 1,
+                });
+            }
+        }
+        AppState::Playing => {
+            if action_state.any_pressed() || *menu_screen.get() != MenuScreen::Closed {
+                attract_mode.idle_timer.reset();
+                return;
+            }
+            if attract_mode.idle_timer.tick(time.delta()).finished() {
+                attract_mode.idle_timer.reset();
+                leave_run(
+                    &mut next_app_state,
+                    &mut next_menu_screen,
+                    &mut new_run_events,
+                    editor.is_some(),
+                );
+            }
+        }
+        AppState::Editing => {}
+    }
+}
@@ -1,64 +1,50 @@
 use bevy::prelude::*;
 
 use crate::{
-    components::{BorderTile, Obstacle},
     constants::{
-        BORDERTILE_SPEED, BORDERTILE_WIDTH, DEFAULT_BORDERTILE_HEIGHT, FLUCTUATION_PER_FRAME,
-        MAX_BORDERTILE_HEIGHT, MIN_BORDERTILE_HEIGHT, WINDOW_HEIGHT, WINDOW_WIDTH,
+        BORDERTILE_SPEED, BORDERTILE_WIDTH, FLUCTUATION_PER_FRAME, MAX_BORDERTILE_HEIGHT,
+        MIN_BORDERTILE_HEIGHT,
     },
     enums::FluctuatingDirection,
-    resources::{BorderTileCurrentHeight, BorderTileFluctuator, GameState},
+    levels::{course_distance, Level},
+    resources::{
+        BorderTileCurrentHeight, BorderTileFluctuator, GameState, LevelProgress, Playfield,
+    },
+    terrain::Terrain,
 };
 
-pub fn spawn_init_border(mut commands: Commands) {
-    let num_border_rects = WINDOW_WIDTH / BORDERTILE_WIDTH;
-    for i in 0..=num_border_rects as u32 {
-        // Top border.
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.3, 0.8, 0.3),
-                custom_size: Some(Vec2::new(BORDERTILE_WIDTH, DEFAULT_BORDERTILE_HEIGHT)),
-                ..Default::default()
-            },
-            Transform::from_xyz(
-                if i == 0 {
-                    -WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH * 0.5
-                } else {
-                    -WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH * 0.5 + (BORDERTILE_WIDTH * i as f32)
-                },
-                WINDOW_HEIGHT * 0.5 - DEFAULT_BORDERTILE_HEIGHT * 0.5,
-                1.0,
-            ),
-            BorderTile {
-                height: DEFAULT_BORDERTILE_HEIGHT,
-            },
-        ));
-        // Bottom Border.
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.3, 0.8, 0.3),
-                custom_size: Some(Vec2::new(BORDERTILE_WIDTH, DEFAULT_BORDERTILE_HEIGHT)),
-                ..Default::default()
-            },
-            Transform::from_xyz(
-                if i == 0 {
-                    -WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH * 0.5
-                } else {
-                    -WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH * 0.5 + (BORDERTILE_WIDTH * i as f32)
-                },
-                -WINDOW_HEIGHT * 0.5 + DEFAULT_BORDERTILE_HEIGHT * 0.5,
-                1.0,
-            ),
-            BorderTile {
-                height: DEFAULT_BORDERTILE_HEIGHT,
-            },
-        ));
+pub fn spawn_init_border(
+    mut terrain: ResMut<Terrain>,
+    playfield: Res<Playfield>,
+    level: Option<Res<Level>>,
+    level_progress: Res<LevelProgress>,
+) {
+    spawn_full_border(&mut terrain, &playfield, level.as_deref(), &level_progress);
+}
+
+// Lines the whole playfield with border, of the default height or the ones a level starts with.
+pub fn spawn_full_border(
+    terrain: &mut Terrain,
+    playfield: &Playfield,
+    level: Option<&Level>,
+    level_progress: &LevelProgress,
+) {
+    terrain.clear();
+    let columns = playfield.width / BORDERTILE_WIDTH;
+    for i in 0..=columns as u32 {
+        let x = -playfield.half_width() + BORDERTILE_WIDTH * 0.5 + (BORDERTILE_WIDTH * i as f32);
+        let heights = level.map_or_else(BorderTileCurrentHeight::default, |level| {
+            level.borders_at(course_distance(x, level_progress))
+        });
+        terrain.push(x, &heights);
     }
 }
 
-pub fn spawn_bordertiles(
-    mut commands: Commands,
+// The border grows on the right, fluctuating between its lowest and highest.
+pub fn extend_border(
     time: Res<Time<Fixed>>,
+    playfield: Res<Playfield>,
+    mut terrain: ResMut<Terrain>,
     mut game_state: ResMut<GameState>,
     mut bordertile_fluctuator: ResMut<BorderTileFluctuator>,
     mut bordertile_cur_height: ResMut<BorderTileCurrentHeight>,
@@ -94,62 +80,27 @@ pub fn spawn_bordertiles(
             }
         }
 
-        // Spawn the top bordertile.
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.3, 0.8, 0.3),
-                custom_size: Some(Vec2::new(
-                    BORDERTILE_WIDTH,
-                    bordertile_cur_height.top_border,
-                )),
-                ..Default::default()
-            },
-            Transform::from_xyz(
-                WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH,
-                WINDOW_HEIGHT * 0.5 - bordertile_cur_height.top_border * 0.5,
-                1.0,
-            ),
-            BorderTile {
-                height: bordertile_cur_height.top_border,
-            },
-        ));
-        // Spawn the bottom bordertile.
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.3, 0.8, 0.3),
-                custom_size: Some(Vec2::new(
-                    BORDERTILE_WIDTH,
-                    bordertile_cur_height.bottom_border,
-                )),
-                ..Default::default()
-            },
-            Transform::from_xyz(
-                WINDOW_WIDTH * 0.5 + BORDERTILE_WIDTH,
-                -WINDOW_HEIGHT * 0.5 + bordertile_cur_height.bottom_border * 0.5,
-                1.0,
-            ),
-            BorderTile {
-                height: bordertile_cur_height.bottom_border,
-            },
-        ));
+        // A playfield shrunk mid-run puts the edge left of the newest sample, new ones go after it.
+        let edge = playfield.half_width() + BORDERTILE_WIDTH;
+        let x = terrain
+            .last()
+            .map_or(edge, |last| edge.max(last.x + BORDERTILE_WIDTH));
+        terrain.push(x, &bordertile_cur_height);
     }
 }
 
-pub fn bordertile_movement(
-    mut commands: Commands,
+pub fn border_movement(
     time: Res<Time>,
-    mut bordertile_query: Query<(Entity, &mut Transform), (With<BorderTile>, Without<Obstacle>)>,
+    playfield: Res<Playfield>,
     game_state: Res<GameState>,
+    mut terrain: ResMut<Terrain>,
 ) {
     if game_state.game_over {
         return;
     }
 
-    for (entity, mut transform) in bordertile_query.iter_mut() {
-        transform.translation.x -= BORDERTILE_SPEED * time.delta_secs();
-
-        if transform.translation.x < (-WINDOW_WIDTH * 0.5 - BORDERTILE_WIDTH * 0.5) {
-            commands.entity(entity).despawn();
-        }
-    }
+    terrain.scroll(
+        BORDERTILE_SPEED * time.delta_secs(),
+        -playfield.half_width(),
+    );
 }
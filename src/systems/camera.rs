@@ -0,0 +1,94 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    components::{Copter, MainCamera},
+    constants::{
+        CAMERA_FOLLOW_SMOOTHING, CRASH_TRAUMA, SHAKE_MAX_OFFSET, SHAKE_MAX_ROTATION,
+        SHAKE_TRAUMA_DECAY, ZOOM_PULSE_STRENGTH,
+    },
+    events::{CrashEvent, NearMissEvent},
+    resources::{CameraEffects, CameraEffectsSettings},
+};
+
+pub fn trigger_camera_effects(
+    mut crash_events: EventReader<CrashEvent>,
+    mut near_miss_events: EventReader<NearMissEvent>,
+    settings: Res<CameraEffectsSettings>,
+    mut camera_effects: ResMut<CameraEffects>,
+) {
+    for _ in crash_events.read() {
+        if settings.shake_enabled() {
+            camera_effects.add_trauma(CRASH_TRAUMA);
+        }
+    }
+
+    // Several near-misses in the same frame still only pulse once.
+    if near_miss_events.read().count() > 0 && settings.zoom_pulse_enabled() {
+        camera_effects.zoom_pulse_timer.reset();
+    }
+}
+
+/*
+    Trauma based screen shake:
+        Every crash adds trauma, which decays linearly over time. The camera is
+        offset and rotated by a random amount proportional to trauma squared, so
+        that small amounts of trauma barely move the camera while big hits shake
+        it hard, and the shake eases out instead of stopping abruptly.
+*/
+pub fn apply_camera_effects(
+    time: Res<Time>,
+    settings: Res<CameraEffectsSettings>,
+    mut camera_effects: ResMut<CameraEffects>,
+    copter_query: Query<&Transform, (With<Copter>, Without<MainCamera>)>,
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+
+    // Follow the copters' mean y position, but only slightly.
+    let copters: Vec<_> = copter_query
+        .iter()
+        .map(|transform| transform.translation.y)
+        .collect();
+    let target_y = if settings.follow_enabled() && !copters.is_empty() {
+        copters.iter().sum::<f32>() / copters.len() as f32 * settings.follow_factor
+    } else {
+        0.0
+    };
+    let smoothing = (CAMERA_FOLLOW_SMOOTHING * time.delta_secs()).min(1.0);
+    camera_effects.follow_y += (target_y - camera_effects.follow_y) * smoothing;
+
+    camera_effects.trauma =
+        (camera_effects.trauma - SHAKE_TRAUMA_DECAY * time.delta_secs()).max(0.0);
+    if !settings.shake_enabled() {
+        camera_effects.trauma = 0.0;
+    }
+
+    let shake = camera_effects.trauma * camera_effects.trauma;
+    let (offset, rotation) = if shake > 0.0 {
+        let mut rng = rand::rng();
+        (
+            Vec2::new(rng.random_range(-1.0..=1.0), rng.random_range(-1.0..=1.0))
+                * SHAKE_MAX_OFFSET
+                * shake,
+            rng.random_range(-1.0..=1.0) * SHAKE_MAX_ROTATION * shake,
+        )
+    } else {
+        (Vec2::ZERO, 0.0)
+    };
+
+    camera_transform.translation.x = offset.x;
+    camera_transform.translation.y = camera_effects.follow_y + offset.y;
+    camera_transform.rotation = Quat::from_rotation_z(rotation);
+
+    // Zoom in quickly and ease back out over the lifetime of the pulse.
+    camera_effects.zoom_pulse_timer.tick(time.delta());
+    projection.scale = if settings.zoom_pulse_enabled() {
+        let remaining = 1.0 - camera_effects.zoom_pulse_timer.fraction();
+        1.0 - ZOOM_PULSE_STRENGTH * remaining * remaining
+    } else {
+        1.0
+    };
+}
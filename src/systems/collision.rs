@@ -1,9 +1,15 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 
 use crate::{
-    components::{BorderTile, Copter, Obstacle},
-    constants::{COPTER_SIZE, WINDOW_HEIGHT},
-    resources::GameState,
+    broadphase::BroadPhase,
+    components::{Copter, NearMissCandidate, NearMissCombo, Obstacle, Passed, Score},
+    constants::{COPTER_SIZE, NEAR_MISS_MARGIN, NEAR_MISS_POINTS},
+    enums::CrashCause,
+    events::{CrashEvent, NearMissEvent},
+    resources::{GameState, Playfield, RunStats},
+    terrain::Terrain,
 };
 
 /*
@@ -40,45 +46,299 @@ fn collide(
     false
 }
 
+/*
+    Wall check:
+        The walls are a height-field, so rather than looking at every piece
+        of them, take the highest either wall reaches under the copter's
+        width. The copter crashes once it pokes past the tip of that, while
+        merely touching it is still fine.
+*/
+
+// Vertical gap between the copter and the closer of the two walls, negative when it pokes into one.
+fn wall_gap(copter_pos: &Vec3, playfield: &Playfield, terrain: &Terrain) -> (f32, CrashCause) {
+    let heights = terrain.max_heights(
+        copter_pos.x - COPTER_SIZE.x * 0.5,
+        copter_pos.x + COPTER_SIZE.x * 0.5,
+    );
+    let gap_above =
+        (playfield.half_height() - heights.top_border) - (copter_pos.y + COPTER_SIZE.y * 0.5);
+    let gap_below =
+        (copter_pos.y - COPTER_SIZE.y * 0.5) - (-playfield.half_height() + heights.bottom_border);
+
+    if gap_above < gap_below {
+        (gap_above, CrashCause::TopBorder)
+    } else {
+        (gap_below, CrashCause::BottomBorder)
+    }
+}
+
+// What the copter at a position crashed into, if anything.
+// Only obstacles the broad-phase finds around the copter are checked.
+fn crash_cause(
+    copter_pos: &Vec3,
+    playfield: &Playfield,
+    terrain: &Terrain,
+    broad_phase: &BroadPhase,
+    obstacle_query: &Query<(&Transform, &Sprite), With<Obstacle>>,
+) -> Option<CrashCause> {
+    let candidates = broad_phase.candidates(
+        copter_pos.x - COPTER_SIZE.x * 0.5,
+        copter_pos.x + COPTER_SIZE.x * 0.5,
+    );
+    for (obstacle_transfom, obstacle_sprite) in obstacle_query.iter_many(candidates) {
+        let obstacle_size = obstacle_sprite.custom_size.unwrap_or(Vec2::ONE);
+        let obstacle_pos = obstacle_transfom.translation;
+
+        if collide(copter_pos, &COPTER_SIZE, &obstacle_pos, &obstacle_size) {
+            return Some(CrashCause::Obstacle);
+        }
+    }
+
+    let (gap, wall) = wall_gap(copter_pos, playfield, terrain);
+    (gap < 0.0).then_some(wall)
+}
+
+// The run is over once every copter has crashed.
+#[allow(clippy::too_many_arguments)]
 pub fn collision_detection(
     mut game_state: ResMut<GameState>,
-    copter_query: Query<&mut Transform, (With<Copter>, Without<Obstacle>)>,
+    mut crash_events: EventWriter<CrashEvent>,
+    playfield: Res<Playfield>,
+    terrain: Res<Terrain>,
+    broad_phase: Res<BroadPhase>,
+    mut copter_query: Query<(&mut Copter, &Transform), Without<Obstacle>>,
     obstacle_query: Query<(&Transform, &Sprite), With<Obstacle>>,
-    bordertile_query: Query<(&Transform, &BorderTile), (Without<Obstacle>, Without<Copter>)>,
 ) {
     if game_state.game_over {
         return;
     }
 
-    if let Ok(copter_transform) = copter_query.get_single() {
-        let copter_pos = copter_transform.translation;
+    for (mut copter, copter_transform) in copter_query.iter_mut() {
+        if copter.crashed() {
+            continue;
+        }
+
+        let cause = crash_cause(
+            &copter_transform.translation,
+            &playfield,
+            &terrain,
+            &broad_phase,
+            &obstacle_query,
+        );
+        if cause.is_some() {
+            copter.crash_cause = cause;
+            crash_events.send(CrashEvent);
+        }
+    }
 
-        for (obstacle_transfom, obstacle_sprite) in obstacle_query.iter() {
-            let obstacle_size = obstacle_sprite.custom_size.unwrap_or(Vec2::ONE);
-            let obstacle_pos = obstacle_transfom.translation;
+    if !copter_query.is_empty() && copter_query.iter().all(|(copter, _)| copter.crashed()) {
+        game_state.game_over = true;
+    }
+}
 
-            if collide(&copter_pos, &COPTER_SIZE, &obstacle_pos, &obstacle_size) {
-                game_state.game_over = true;
-                return;
-            }
+/*
+    Near-miss check:
+        While an obstacle overlaps the copter on the x-axis, measure the
+        vertical gap between them. If it ever shrinks to within
+        NEAR_MISS_MARGIN, the obstacle becomes a candidate of that copter.
+        Once it is fully behind the copters it is marked as passed, which
+        also counts it towards the score of every copter still flying, and
+        candidates that were not crashed into award bonus points. The walls
+        have no pieces to pass, so grazing them awards bonus points right
+        away, to a copter that did not crash this tick, and is rate limited
+        by a cooldown instead.
+
+        Gaps are only measured to the obstacles the broad-phase finds within
+        NEAR_MISS_MARGIN of a copter. Telling whether an obstacle is behind
+        the copters takes a single comparison, so that is done for each.
+*/
+
+fn vertical_gap(
+    copter_pos: &Vec3,
+    copter_size: &Vec2,
+    collider_pos: &Vec3,
+    collider_size: &Vec2,
+) -> f32 {
+    let gap_above = (collider_pos.y - collider_size.y * 0.5) - (copter_pos.y + copter_size.y * 0.5);
+    let gap_below = (copter_pos.y - copter_size.y * 0.5) - (collider_pos.y + collider_size.y * 0.5);
+    gap_above.max(gap_below)
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn near_miss_detection(
+    mut commands: Commands,
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    mut run_stats: ResMut<RunStats>,
+    mut near_miss_events: EventWriter<NearMissEvent>,
+    playfield: Res<Playfield>,
+    terrain: Res<Terrain>,
+    broad_phase: Res<BroadPhase>,
+    mut copter_query: Query<(Entity, &Copter, &Transform, &mut Score, &mut NearMissCombo)>,
+    obstacle_query: Query<
+        (Entity, &Transform, &Sprite, Option<&NearMissCandidate>),
+        (With<Obstacle>, Without<Passed>),
+    >,
+) {
+    if game_state.game_over {
+        return;
+    }
+
+    for (_, copter, _, _, mut combo) in copter_query.iter_mut() {
+        if !copter.crashed() {
+            combo.combo_timer.tick(time.delta());
+            combo.border_cooldown.tick(time.delta());
         }
+    }
 
-        let copter_xaxis_range =
-            copter_pos.x - COPTER_SIZE.x * 0.5..=copter_pos.x + COPTER_SIZE.x * 0.5;
+    // Obstacles that gained candidates this tick, along with the ones they had before.
+    let mut new_candidates: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (copter_entity, copter, copter_transform, ..) in copter_query.iter() {
+        if copter.crashed() {
+            continue;
+        }
 
-        for (bordertile_transform, bordertile) in bordertile_query.iter() {
-            let is_top_bordertile = bordertile_transform.translation.y.is_sign_positive();
-            let colliding_with_top_bordertile = is_top_bordertile
-                && copter_pos.y > WINDOW_HEIGHT * 0.5 - bordertile.height - COPTER_SIZE.y * 0.5;
-            let colliding_with_bottom_bordertile = !is_top_bordertile
-                && copter_pos.y < -WINDOW_HEIGHT * 0.5 + bordertile.height + COPTER_SIZE.y * 0.5;
+        let copter_pos = copter_transform.translation;
+        let nearby = broad_phase.candidates(
+            copter_pos.x - COPTER_SIZE.x * 0.5 - NEAR_MISS_MARGIN,
+            copter_pos.x + COPTER_SIZE.x * 0.5 + NEAR_MISS_MARGIN,
+        );
+        for (entity, transform, sprite, candidate) in obstacle_query.iter_many(nearby) {
+            let collider_size = sprite.custom_size.unwrap_or(Vec2::ONE);
+            let collider_pos = transform.translation;
+            if (collider_pos.x - copter_pos.x).abs() >= (COPTER_SIZE.x + collider_size.x) * 0.5 {
+                continue;
+            }
 
-            if copter_xaxis_range.contains(&bordertile_transform.translation.x)
-                && (colliding_with_top_bordertile || colliding_with_bottom_bordertile)
+            let gap = vertical_gap(&copter_pos, &COPTER_SIZE, &collider_pos, &collider_size);
+            run_stats.record_gap(gap);
+            if gap > NEAR_MISS_MARGIN
+                || candidate.is_some_and(|candidate| candidate.0.contains(&copter_entity))
             {
-                game_state.game_over = true;
-                return;
+                continue;
             }
+            new_candidates
+                .entry(entity)
+                .or_insert_with(|| candidate.map_or_else(Vec::new, |candidate| candidate.0.clone()))
+                .push(copter_entity);
+        }
+    }
+    for (entity, candidates) in new_candidates {
+        commands
+            .entity(entity)
+            .insert(NearMissCandidate(candidates));
+    }
+
+    // Copters all fly at the same x, so they pass an obstacle at the same time.
+    let behind = copter_query
+        .iter()
+        .map(|(_, _, copter_transform, ..)| copter_transform.translation.x - COPTER_SIZE.x * 0.5)
+        .fold(f32::INFINITY, f32::min);
+    for (entity, transform, sprite, candidate) in obstacle_query.iter() {
+        let collider_size = sprite.custom_size.unwrap_or(Vec2::ONE);
+        if transform.translation.x + collider_size.x * 0.5 >= behind {
+            continue;
         }
+        commands.entity(entity).insert(Passed);
+
+        for (copter_entity, copter, copter_transform, mut score, mut combo) in
+            copter_query.iter_mut()
+        {
+            if copter.crashed() {
+                continue;
+            }
+            score.obstacles_passed += 1;
+
+            if !candidate.is_some_and(|candidate| candidate.0.contains(&copter_entity)) {
+                continue;
+            }
+
+            let points = NEAR_MISS_POINTS * combo.register();
+            score.near_miss_points += points;
+            near_miss_events.send(NearMissEvent {
+                position: copter_transform.translation,
+                points,
+            });
+        }
+    }
+
+    for (_, copter, copter_transform, mut score, mut combo) in copter_query.iter_mut() {
+        if copter.crashed() {
+            continue;
+        }
+
+        let copter_pos = copter_transform.translation;
+        let (gap, _) = wall_gap(&copter_pos, &playfield, &terrain);
+        run_stats.record_gap(gap);
+        if gap > NEAR_MISS_MARGIN || !combo.border_cooldown.finished() {
+            continue;
+        }
+        combo.border_cooldown.reset();
+
+        let points = NEAR_MISS_POINTS * combo.register();
+        score.near_miss_points += points;
+        near_miss_events.send(NearMissEvent {
+            position: copter_pos,
+            points,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBSTACLE_SIZE: Vec2 = Vec2::new(100.0, 250.0);
+
+    fn collides_at(obstacle_pos: Vec3) -> bool {
+        collide(&Vec3::ZERO, &COPTER_SIZE, &obstacle_pos, &OBSTACLE_SIZE)
+    }
+
+    #[test]
+    fn overlapping_boxes_collide() {
+        assert!(collides_at(Vec3::new(10.0, 20.0, 0.0)));
+    }
+
+    #[test]
+    fn distant_boxes_do_not_collide() {
+        assert!(!collides_at(Vec3::new(0.0, 300.0, 0.0)));
+        assert!(!collides_at(Vec3::new(200.0, 0.0, 0.0)));
+    }
+
+    // The y-axis check is inclusive, so boxes touching top to bottom collide.
+    #[test]
+    fn boxes_touching_on_y_collide() {
+        let touching = (COPTER_SIZE.y + OBSTACLE_SIZE.y) * 0.5;
+        assert!(collides_at(Vec3::new(0.0, touching, 0.0)));
+        assert!(collides_at(Vec3::new(0.0, -touching, 0.0)));
+        assert!(!collides_at(Vec3::new(0.0, touching + 0.01, 0.0)));
+    }
+
+    // The x-axis check is strict, so boxes touching side by side don't.
+    #[test]
+    fn boxes_touching_on_x_do_not_collide() {
+        let touching = (COPTER_SIZE.x + OBSTACLE_SIZE.x) * 0.5;
+        assert!(!collides_at(Vec3::new(touching, 0.0, 0.0)));
+        assert!(!collides_at(Vec3::new(-touching, 0.0, 0.0)));
+        assert!(collides_at(Vec3::new(touching - 0.01, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn boxes_touching_at_a_corner_do_not_collide() {
+        let touching = (COPTER_SIZE + OBSTACLE_SIZE) * 0.5;
+        assert!(!collides_at(touching.extend(0.0)));
+    }
+
+    #[test]
+    fn vertical_gap_is_measured_to_the_closer_side() {
+        let obstacle_pos = Vec3::new(0.0, 200.0, 0.0);
+        let gap = vertical_gap(&Vec3::ZERO, &COPTER_SIZE, &obstacle_pos, &OBSTACLE_SIZE);
+        assert_eq!(gap, 200.0 - OBSTACLE_SIZE.y * 0.5 - COPTER_SIZE.y * 0.5);
+    }
+
+    #[test]
+    fn vertical_gap_is_negative_when_overlapping() {
+        let gap = vertical_gap(&Vec3::ZERO, &COPTER_SIZE, &Vec3::ZERO, &OBSTACLE_SIZE);
+        assert!(gap < 0.0);
     }
 }
@@ -2,12 +2,21 @@ use bevy::prelude::*;
 
 use crate::{
     components::Copter,
-    constants::{GRAVITY, LIFT, NEGATE_DELAY},
-    resources::GameState,
+    constants::{GRAVITY, LIFT, MAX_COPTER_VELOCITY, NEGATE_DELAY},
+    resources::{ActionState, GameState},
 };
 
+// Copters of gamers sharing this machine lift with their own actions.
+pub fn gamer_lift(action_state: Res<ActionState>, mut copter_query: Query<&mut Copter>) {
+    for mut copter in copter_query.iter_mut() {
+        copter.lift = copter
+            .player
+            .lift()
+            .is_some_and(|action| action_state.pressed(action));
+    }
+}
+
 pub fn copter_movement(
-    mouse_input: Res<ButtonInput<MouseButton>>,
     time: Res<Time>,
     mut copter_query: Query<(&mut Copter, &mut Transform)>,
     game_state: Res<GameState>,
@@ -16,10 +25,14 @@ pub fn copter_movement(
         return;
     }
 
-    if let Ok((mut copter, mut transform)) = copter_query.get_single_mut() {
+    for (mut copter, mut transform) in copter_query.iter_mut() {
+        if copter.crashed() {
+            continue;
+        }
+
         copter.velocity -= GRAVITY * time.delta_secs() + NEGATE_DELAY;
 
-        if mouse_input.pressed(MouseButton::Left) {
+        if copter.lift {
             copter.velocity += LIFT * time.delta_secs() + NEGATE_DELAY;
         }
 
@@ -29,7 +42,9 @@ pub fn copter_movement(
             velocity and quickly rises to the sky. This helps the game feel less laggy and more
             responsive to gamer inputs.
         */
-        copter.velocity = copter.velocity.clamp(-500.0, 500.0);
+        copter.velocity = copter
+            .velocity
+            .clamp(-MAX_COPTER_VELOCITY, MAX_COPTER_VELOCITY);
 
         transform.translation.y += copter.velocity * time.delta_secs();
     }
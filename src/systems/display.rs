@@ -0,0 +1,128 @@
+use bevy::{prelude::*, render::camera::ScalingMode, window::PrimaryWindow};
+
+use crate::{
+    components::{Background, Copter, Letterbox, MainCamera, Obstacle},
+    constants::{PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH},
+    enums::{Action, PlayfieldScaling},
+    lan::LanSession,
+    resources::{ActionState, CameraEffectsSettings, Playfield},
+    settings::Settings,
+};
+
+pub fn toggle_fullscreen(action_state: Res<ActionState>, mut settings: ResMut<Settings>) {
+    if action_state.just_pressed(Action::ToggleFullscreen) {
+        settings.display.fullscreen = !settings.display.fullscreen;
+    }
+}
+
+pub fn apply_window_settings(
+    settings: Res<Settings>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Ok(mut window) = window_query.get_single_mut() {
+        let mode = settings.display.window_mode();
+        if window.mode != mode {
+            window.mode = mode;
+        }
+        let present_mode = settings.display.present_mode();
+        if window.present_mode != present_mode {
+            window.present_mode = present_mode;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn apply_display_settings(
+    settings: Res<Settings>,
+    mut camera_effects_settings: ResMut<CameraEffectsSettings>,
+    mut sprite_query: Query<
+        (&mut Sprite, Has<Background>, Option<&Copter>),
+        Or<(With<Background>, With<Copter>, With<Obstacle>)>,
+    >,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    *camera_effects_settings = CameraEffectsSettings {
+        screen_shake: settings.display.screen_shake,
+        zoom_pulse: settings.display.zoom_pulse,
+        follow: settings.display.camera_follow,
+        follow_factor: settings.display.follow_factor,
+        reduced_motion: settings.display.reduced_motion,
+    };
+
+    let theme = settings.display.color_theme;
+    for (mut sprite, is_background, copter) in sprite_query.iter_mut() {
+        sprite.color = match copter {
+            _ if is_background => theme.background(),
+            Some(copter) => theme.copter(copter.player),
+            None => theme.wall(),
+        };
+    }
+}
+
+/*
+    The camera always shows the whole playfield, scaled to the largest size that
+    fits the window. Whatever is left over on the sides is either covered by the
+    letterbox bars or, when extending, turned into more playfield so that
+    obstacles and walls come in and leave at the real edges of the screen.
+*/
+#[allow(clippy::too_many_arguments)]
+pub fn fit_playfield(
+    settings: Res<Settings>,
+    lan_session: Option<Res<LanSession>>,
+    window_query: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut playfield: ResMut<Playfield>,
+    mut camera_query: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut letterbox_query: Query<&mut Visibility, With<Letterbox>>,
+    mut background_query: Query<&mut Sprite, With<Background>>,
+    mut was_racing: Local<bool>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+    let racing = lan_session.is_some();
+    if !window.is_changed() && !settings.is_changed() && racing == *was_racing {
+        return;
+    }
+    *was_racing = racing;
+
+    // Every machine in a LAN race must spawn the world at the same edges.
+    let scaling = if racing {
+        PlayfieldScaling::Letterbox
+    } else {
+        settings.display.playfield_scaling
+    };
+    let aspect_ratio = window.width() / window.height().max(1.0);
+    let width = match scaling {
+        PlayfieldScaling::Letterbox => PLAYFIELD_WIDTH,
+        PlayfieldScaling::Extend => (PLAYFIELD_HEIGHT * aspect_ratio).max(PLAYFIELD_WIDTH),
+    };
+    if playfield.width != width {
+        playfield.width = width;
+    }
+
+    for mut projection in camera_query.iter_mut() {
+        projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: playfield.width,
+            min_height: playfield.height,
+        };
+    }
+
+    for mut visibility in letterbox_query.iter_mut() {
+        *visibility = match scaling {
+            PlayfieldScaling::Letterbox => Visibility::Inherited,
+            PlayfieldScaling::Extend => Visibility::Hidden,
+        };
+    }
+
+    // Oversized so that camera shake and follow never reveal its edges.
+    for mut sprite in background_query.iter_mut() {
+        sprite.custom_size = Some(Vec2::new(playfield.width, playfield.height) * 1.5);
+    }
+}
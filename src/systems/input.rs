@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+
+use crate::{
+    enums::{Action, GameMode, InputBinding},
+    resources::{ActionState, SelectedGameMode},
+    settings::Settings,
+};
+
+// Translate raw keyboard, mouse, gamepad and touch input into actions.
+pub fn update_action_state(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<(Entity, &Gamepad)>,
+    settings: Res<Settings>,
+    selected_mode: Res<SelectedGameMode>,
+    mut action_state: ResMut<ActionState>,
+) {
+    action_state.clear();
+
+    let mut gamepads: Vec<_> = gamepads.iter().collect();
+    gamepads.sort_by_key(|(entity, _)| *entity);
+    let versus = selected_mode.0 == GameMode::Versus;
+
+    for action in Action::ALL {
+        // In versus the second gamepad lifts player two, and only player two.
+        let action_gamepads: Vec<_> = gamepads
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| match action {
+                Action::Lift => !versus || *index != 1,
+                Action::LiftPlayerTwo => *index == 1,
+                _ => true,
+            })
+            .map(|(_, (_, gamepad))| *gamepad)
+            .collect();
+
+        for binding in settings.input.get(action) {
+            let (pressed, just_pressed) = match *binding {
+                InputBinding::Key(key) => (
+                    keyboard_input.pressed(key),
+                    keyboard_input.just_pressed(key),
+                ),
+                InputBinding::Mouse(button) => (
+                    mouse_input.pressed(button),
+                    mouse_input.just_pressed(button),
+                ),
+                InputBinding::Gamepad(button) => (
+                    action_gamepads
+                        .iter()
+                        .any(|gamepad| gamepad.pressed(button)),
+                    action_gamepads
+                        .iter()
+                        .any(|gamepad| gamepad.just_pressed(button)),
+                ),
+            };
+
+            if just_pressed {
+                action_state.just_press(action);
+            } else if pressed {
+                action_state.press(action);
+            }
+        }
+    }
+
+    // Touch screens are not rebindable: holding a finger lifts and tapping confirms.
+    if touches.iter().next().is_some() {
+        action_state.press(Action::Lift);
+    }
+    if touches.any_just_pressed() {
+        action_state.just_press(Action::Lift);
+        action_state.just_press(Action::Confirm);
+    }
+}
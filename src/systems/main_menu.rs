@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{GameModeItem, HighScoresBack, MainMenuItem},
+    daily,
+    editor::LevelEditor,
+    enums::{Action, AppState, GameMode, MenuInput, MenuScreen},
+    events::{MenuEvent, NewRunEvent},
+    highscores::HighScores,
+    resources::{ActionState, CourseSeed, GameState, SelectedGameMode},
+    systems::menu::{menu_hint, menu_root, menu_title, set_item_label, spawn_menu_item},
+};
+
+pub fn start_run(
+    next_app_state: &mut NextState<AppState>,
+    next_menu_screen: &mut NextState<MenuScreen>,
+    new_run_events: &mut EventWriter<NewRunEvent>,
+    mode: GameMode,
+    course_seed: &CourseSeed,
+) {
+    let seed = course_seed.for_mode(mode);
+
+    next_app_state.set(AppState::Playing);
+    next_menu_screen.set(MenuScreen::Closed);
+    new_run_events.send(NewRunEvent {
+        seed,
+        players: mode.players(),
+    });
+}
+
+/*
+    Leaves the run and starts a fresh world for the attract mode behind the
+    main menu. Test flights go straight back to the level editor instead,
+    which puts the world back where the flight started.
+*/
+pub fn leave_run(
+    next_app_state: &mut NextState<AppState>,
+    next_menu_screen: &mut NextState<MenuScreen>,
+    new_run_events: &mut EventWriter<NewRunEvent>,
+    editing: bool,
+) {
+    if editing {
+        next_app_state.set(AppState::Editing);
+        next_menu_screen.set(MenuScreen::Closed);
+        return;
+    }
+
+    next_app_state.set(AppState::MainMenu);
+    next_menu_screen.set(MenuScreen::Main);
+    new_run_events.send(NewRunEvent {
+        seed: rand::random(),
+        players: 1,
+    });
+}
+
+pub fn setup_main_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuScreen::Main))
+        .insert(BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.35)))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Copter"));
+            for (index, item) in [
+                MainMenuItem::Play,
+                MainMenuItem::GameModes,
+                MainMenuItem::HighScores,
+                MainMenuItem::Settings,
+                MainMenuItem::Quit,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                spawn_menu_item(parent, index, item);
+            }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn main_menu(
+    selected_mode: Res<SelectedGameMode>,
+    course_seed: Res<CourseSeed>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<(&MainMenuItem, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut new_run_events: EventWriter<NewRunEvent>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    for (item, children) in item_query.iter() {
+        let label = match item {
+            MainMenuItem::Play => match selected_mode.0.leaderboard_day() {
+                Some(day) => format!("Play ({:?} {})", selected_mode.0, daily::format_date(day)),
+                None => format!("Play ({:?})", selected_mode.0),
+            },
+            MainMenuItem::GameModes => "Game modes".to_string(),
+            MainMenuItem::HighScores => "High scores".to_string(),
+            MainMenuItem::Settings => "Settings".to_string(),
+            MainMenuItem::Quit => "Quit".to_string(),
+        };
+        set_item_label(children, &mut text_query, label);
+    }
+
+    for event in menu_events.read() {
+        let (Ok((item, _)), MenuInput::Activate) = (item_query.get(event.item), &event.input)
+        else {
+            continue;
+        };
+        match item {
+            MainMenuItem::Play => start_run(
+                &mut next_app_state,
+                &mut next_menu_screen,
+                &mut new_run_events,
+                selected_mode.0,
+                &course_seed,
+            ),
+            MainMenuItem::GameModes => next_menu_screen.set(MenuScreen::GameModes),
+            MainMenuItem::HighScores => next_menu_screen.set(MenuScreen::HighScores),
+            MainMenuItem::Settings => next_menu_screen.set(MenuScreen::Settings),
+            MainMenuItem::Quit => {
+                app_exit_events.send(AppExit::Success);
+            }
+        }
+    }
+}
+
+pub fn setup_game_modes_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuScreen::GameModes))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Game modes"));
+            for (index, mode) in GameMode::ALL.into_iter().enumerate() {
+                spawn_menu_item(parent, index, GameModeItem::Mode(mode));
+            }
+            spawn_menu_item(parent, GameMode::ALL.len(), GameModeItem::Back);
+        });
+}
+
+pub fn game_modes_menu(
+    action_state: Res<ActionState>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<(&GameModeItem, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut selected_mode: ResMut<SelectedGameMode>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if action_state.just_pressed(Action::Back) {
+        next_menu_screen.set(MenuScreen::Main);
+    }
+
+    for event in menu_events.read() {
+        let (Ok((item, _)), MenuInput::Activate) = (item_query.get(event.item), &event.input)
+        else {
+            continue;
+        };
+        match item {
+            GameModeItem::Mode(mode) => {
+                selected_mode.0 = *mode;
+                next_menu_screen.set(MenuScreen::Main);
+            }
+            GameModeItem::Back => next_menu_screen.set(MenuScreen::Main),
+        }
+    }
+
+    for (item, children) in item_query.iter() {
+        let label = match item {
+            GameModeItem::Mode(mode) => {
+                let marker = if selected_mode.0 == *mode { "> " } else { "" };
+                format!("{marker}{mode:?}: {}", mode.description())
+            }
+            GameModeItem::Back => "Back".to_string(),
+        };
+        set_item_label(children, &mut text_query, label);
+    }
+}
+
+pub fn setup_high_scores_menu(
+    mut commands: Commands,
+    selected_mode: Res<SelectedGameMode>,
+    high_scores: Res<HighScores>,
+) {
+    let top = high_scores.top(selected_mode.0);
+    let list = if top.is_empty() {
+        "No runs yet.".to_string()
+    } else {
+        top.iter()
+            .enumerate()
+            .map(|(rank, entry)| {
+                format!(
+                    "{:>2}. {:>6}   {:>5} m{}",
+                    rank + 1,
+                    entry.score,
+                    entry.meters,
+                    if entry.official { "   official" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    commands
+        .spawn(menu_root(MenuScreen::HighScores))
+        .with_children(|parent| {
+            let title = match selected_mode.0.leaderboard_day() {
+                Some(day) => format!(
+                    "High scores: {:?} {}",
+                    selected_mode.0,
+                    daily::format_date(day)
+                ),
+                None => format!("High scores: {:?}", selected_mode.0),
+            };
+            parent.spawn(menu_title(&title));
+            parent.spawn(menu_hint(&list));
+            spawn_menu_item(parent, 0, HighScoresBack);
+        });
+}
+
+pub fn high_scores_menu(
+    action_state: Res<ActionState>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<&Children, With<HighScoresBack>>,
+    mut text_query: Query<&mut Text>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    for children in item_query.iter() {
+        set_item_label(children, &mut text_query, "Back".to_string());
+    }
+
+    let activated = menu_events
+        .read()
+        .any(|event| matches!(event.input, MenuInput::Activate));
+    if activated || action_state.just_pressed(Action::Back) {
+        next_menu_screen.set(MenuScreen::Main);
+    }
+}
+
+// Once a run is over, Back returns to the main menu.
+pub fn leave_finished_run(
+    action_state: Res<ActionState>,
+    game_state: Res<GameState>,
+    editor: Option<Res<LevelEditor>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut new_run_events: EventWriter<NewRunEvent>,
+) {
+    if game_state.game_over && action_state.just_pressed(Action::Back) {
+        leave_run(
+            &mut next_app_state,
+            &mut next_menu_screen,
+            &mut new_run_events,
+            editor.is_some(),
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::{MenuItem, PauseItem},
+    editor::LevelEditor,
+    enums::{Action, AppState, MenuInput, MenuScreen},
+    events::{MenuEvent, NewRunEvent},
+    resources::{ActionState, GameState, MenuSelection, Rebinding},
+    systems::main_menu::leave_run,
+};
+
+const ITEM_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+const SELECTED_ITEM_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+
+// Full screen column that every menu screen is built in. Despawned when leaving the screen.
+pub fn menu_root(screen: MenuScreen) -> impl Bundle {
+    (
+        Node {
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            row_gap: Val::Px(10.0),
+            ..Default::default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        StateScoped(screen),
+    )
+}
+
+pub fn menu_title(title: &str) -> impl Bundle {
+    (
+        Text::new(title),
+        TextFont {
+            font_size: 48.0,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn menu_hint(hint: &str) -> impl Bundle {
+    (
+        Text::new(hint),
+        TextFont {
+            font_size: 18.0,
+            ..Default::default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+    )
+}
+
+// Items are buttons with a single text child, whose label is kept up to date by each screen.
+pub fn spawn_menu_item(parent: &mut ChildBuilder, index: usize, item: impl Bundle) {
+    parent
+        .spawn((Button, Node::default(), MenuItem(index), item))
+        .with_child((
+            Text::default(),
+            TextFont {
+                font_size: 24.0,
+                ..Default::default()
+            },
+            TextColor(ITEM_COLOR),
+        ));
+}
+
+pub fn reset_menu_selection(mut selection: ResMut<MenuSelection>) {
+    selection.0 = 0;
+}
+
+// Keyboard, gamepad and mouse navigation shared by every menu screen.
+#[allow(clippy::too_many_arguments)]
+pub fn navigate_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    action_state: Res<ActionState>,
+    rebinding: Res<Rebinding>,
+    item_query: Query<(Entity, &MenuItem)>,
+    interaction_query: Query<(&MenuItem, &Interaction), Changed<Interaction>>,
+    mut selection: ResMut<MenuSelection>,
+    mut menu_events: EventWriter<MenuEvent>,
+) {
+    if rebinding.listening.is_some() || item_query.is_empty() {
+        return;
+    }
+
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keyboard_input.just_pressed(key)
+            || gamepads.iter().any(|gamepad| gamepad.just_pressed(button))
+    };
+    let items = item_query.iter().count();
+
+    if pressed(KeyCode::ArrowUp, GamepadButton::DPadUp) {
+        selection.0 = (selection.0 + items - 1) % items;
+    }
+    if pressed(KeyCode::ArrowDown, GamepadButton::DPadDown) {
+        selection.0 = (selection.0 + 1) % items;
+    }
+
+    let mut input = None;
+    if pressed(KeyCode::ArrowLeft, GamepadButton::DPadLeft) {
+        input = Some(MenuInput::Decrease);
+    }
+    if pressed(KeyCode::ArrowRight, GamepadButton::DPadRight) {
+        input = Some(MenuInput::Increase);
+    }
+    if action_state.just_pressed(Action::Confirm) {
+        input = Some(MenuInput::Activate);
+    }
+
+    for (item, interaction) in interaction_query.iter() {
+        match interaction {
+            Interaction::Hovered => selection.0 = item.0,
+            Interaction::Pressed => {
+                selection.0 = item.0;
+                input = Some(MenuInput::Activate);
+            }
+            Interaction::None => {}
+        }
+    }
+
+    if let Some(input) = input {
+        if let Some((entity, _)) = item_query.iter().find(|(_, item)| item.0 == selection.0) {
+            menu_events.send(MenuEvent {
+                item: entity,
+                input,
+            });
+        }
+    }
+}
+
+pub fn highlight_menu_items(
+    selection: Res<MenuSelection>,
+    item_query: Query<(&MenuItem, &Children)>,
+    mut text_query: Query<&mut TextColor>,
+) {
+    for (item, children) in item_query.iter() {
+        for child in children.iter() {
+            if let Ok(mut color) = text_query.get_mut(*child) {
+                color.0 = if item.0 == selection.0 {
+                    SELECTED_ITEM_COLOR
+                } else {
+                    ITEM_COLOR
+                };
+            }
+        }
+    }
+}
+
+// Sets the label of a menu item, which lives in its text child.
+pub fn set_item_label(children: &Children, text_query: &mut Query<&mut Text>, label: String) {
+    for child in children.iter() {
+        if let Ok(mut text) = text_query.get_mut(*child) {
+            if text.0 != label {
+                text.0 = label.clone();
+            }
+        }
+    }
+}
+
+// Pausing stops virtual time, so every system that moves things by delta time freezes.
+// The attract mode behind the main menu keeps running.
+pub fn pause_in_menus(
+    app_state: Res<State<AppState>>,
+    menu_screen: Res<State<MenuScreen>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if *app_state.get() == AppState::Playing && *menu_screen.get() != MenuScreen::Closed {
+        time.pause();
+    } else {
+        time.unpause();
+    }
+}
+
+pub fn open_pause_menu(
+    action_state: Res<ActionState>,
+    game_state: Res<GameState>,
+    menu_screen: Res<State<MenuScreen>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if *menu_screen.get() == MenuScreen::Closed
+        && !game_state.game_over
+        && action_state.just_pressed(Action::Pause)
+    {
+        next_menu_screen.set(MenuScreen::Pause);
+    }
+}
+
+pub fn setup_pause_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuScreen::Pause))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Paused"));
+            spawn_menu_item(parent, 0, PauseItem::Resume);
+            spawn_menu_item(parent, 1, PauseItem::Settings);
+            spawn_menu_item(parent, 2, PauseItem::MainMenu);
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn pause_menu(
+    action_state: Res<ActionState>,
+    editor: Option<Res<LevelEditor>>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<(&PauseItem, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+    mut new_run_events: EventWriter<NewRunEvent>,
+) {
+    for (item, children) in item_query.iter() {
+        let label = match item {
+            PauseItem::Resume => "Resume",
+            PauseItem::Settings => "Settings",
+            PauseItem::MainMenu if editor.is_some() => "Back to the editor",
+            PauseItem::MainMenu => "Main menu",
+        };
+        set_item_label(children, &mut text_query, label.to_string());
+    }
+
+    if action_state.just_pressed(Action::Back) || action_state.just_pressed(Action::Pause) {
+        next_menu_screen.set(MenuScreen::Closed);
+    }
+
+    for event in menu_events.read() {
+        let Ok((item, _)) = item_query.get(event.item) else {
+            continue;
+        };
+        if let MenuInput::Activate = event.input {
+            match item {
+                PauseItem::Resume => next_menu_screen.set(MenuScreen::Closed),
+                PauseItem::Settings => next_menu_screen.set(MenuScreen::Settings),
+                PauseItem::MainMenu => leave_run(
+                    &mut next_app_state,
+                    &mut next_menu_screen,
+                    &mut new_run_events,
+                    editor.is_some(),
+                ),
+            }
+        }
+    }
+}
@@ -1,5 +1,14 @@
+pub mod audio;
+pub mod autopilot;
 pub mod bordertiles;
+pub mod camera;
 pub mod collision;
 pub mod copter;
+pub mod display;
+pub mod input;
+pub mod main_menu;
+pub mod menu;
 pub mod obstacles;
+pub mod rebinding;
+pub mod settings_menu;
 pub mod ui;
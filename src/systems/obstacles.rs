@@ -2,16 +2,24 @@ use bevy::prelude::*;
 use rand::Rng;
 
 use crate::{
-    components::{BorderTile, Obstacle},
-    constants::{OBSTACLE_HEIGHT, OBSTACLE_SPEED, OBSTACLE_WIDTH, WINDOW_HEIGHT, WINDOW_WIDTH},
-    resources::{BorderTileCurrentHeight, GameState},
+    components::Obstacle,
+    constants::{COPTER_SIZE, OBSTACLE_HEIGHT, OBSTACLE_SPEED, OBSTACLE_WIDTH},
+    pool::ColliderPool,
+    resources::{BorderTileCurrentHeight, GameRng, GameState, Playfield, RunStats},
+    settings::Settings,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_obstacles(
     mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
     time: Res<Time>,
+    playfield: Res<Playfield>,
+    settings: Res<Settings>,
     current_bordertile_height: Res<BorderTileCurrentHeight>,
     mut game_state: ResMut<GameState>,
+    mut rng: ResMut<GameRng>,
+    mut run_stats: ResMut<RunStats>,
 ) {
     if game_state.game_over {
         return;
@@ -20,35 +28,52 @@ pub fn spawn_obstacles(
     game_state.obstacle_timer.tick(time.delta());
 
     if game_state.obstacle_timer.just_finished() {
-        let mut rng = rand::rng();
         // Obstacle spawned outside the right wall due to this x-coordinate.
-        let obstacle_x = WINDOW_WIDTH * 0.5 + OBSTACLE_WIDTH * 0.5;
-        // Spawn at any legal y-coord where legal == within the border tiles' current height && doesn't clip the border tiles.
-        let obstacle_y = rng.random_range(
-            (-WINDOW_HEIGHT * 0.5 + current_bordertile_height.bottom_border + OBSTACLE_HEIGHT * 0.5)
+        let obstacle_x = playfield.half_width() + OBSTACLE_WIDTH * 0.5;
+        // Spawn at any legal y-coord where legal == within the border's current height && doesn't clip the walls.
+        let obstacle_y = rng.obstacles.random_range(
+            (-playfield.half_height()
+                + current_bordertile_height.bottom_border
+                + OBSTACLE_HEIGHT * 0.5)
                 * 0.334
-                ..(WINDOW_HEIGHT * 0.5
+                ..(playfield.half_height()
                     - current_bordertile_height.top_border
                     - OBSTACLE_HEIGHT * 0.5)
                     * 0.334,
         );
 
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(0.3, 0.8, 0.3),
-                custom_size: Some(Vec2::new(OBSTACLE_WIDTH, OBSTACLE_HEIGHT)),
-                ..Default::default()
-            },
-            Transform::from_xyz(obstacle_x, obstacle_y, 1.0),
-            Obstacle,
-        ));
+        // Measured against the border being spawned right now, which is close to
+        // what the copter meets once the obstacle reaches it.
+        let gap_above = playfield.half_height()
+            - current_bordertile_height.top_border
+            - (obstacle_y + OBSTACLE_HEIGHT * 0.5);
+        let gap_below = (obstacle_y - OBSTACLE_HEIGHT * 0.5)
+            - (-playfield.half_height() + current_bordertile_height.bottom_border);
+        if gap_above.max(gap_below) < COPTER_SIZE.y {
+            run_stats.impassable_obstacles += 1;
+        }
+
+        pool.spawn(
+            &mut commands,
+            (
+                Sprite {
+                    color: settings.display.color_theme.wall(),
+                    custom_size: Some(Vec2::new(OBSTACLE_WIDTH, OBSTACLE_HEIGHT)),
+                    ..Default::default()
+                },
+                Transform::from_xyz(obstacle_x, obstacle_y, 1.0),
+                Obstacle,
+            ),
+        );
     }
 }
 
 pub fn obstacle_movement(
     mut commands: Commands,
+    mut pool: ResMut<ColliderPool>,
     time: Res<Time>,
-    mut obstacle_query: Query<(Entity, &mut Transform), (With<Obstacle>, Without<BorderTile>)>,
+    mut obstacle_query: Query<(Entity, &mut Transform), With<Obstacle>>,
+    playfield: Res<Playfield>,
     game_state: Res<GameState>,
 ) {
     if game_state.game_over {
@@ -58,9 +83,9 @@ pub fn obstacle_movement(
     for (entity, mut transform) in obstacle_query.iter_mut() {
         transform.translation.x -= OBSTACLE_SPEED * time.delta_secs();
 
-        // If the obstacle has been surpassed and is now outside the screen, despawn it.
-        if transform.translation.x < (-WINDOW_WIDTH * 0.5 - OBSTACLE_WIDTH) {
-            commands.entity(entity).despawn();
+        // If the obstacle has been surpassed and is now outside the screen, park it for reuse.
+        if transform.translation.x < (-playfield.half_width() - OBSTACLE_WIDTH) {
+            pool.park(&mut commands, entity);
         }
     }
 }
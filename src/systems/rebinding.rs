@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::BindingItem,
+    enums::{Action, InputBinding, MenuInput, MenuScreen},
+    events::MenuEvent,
+    resources::{ActionState, MenuSelection, Rebinding},
+    settings::Settings,
+    systems::menu::{menu_hint, menu_root, menu_title, set_item_label, spawn_menu_item},
+};
+
+pub fn setup_bindings_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuScreen::Bindings))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Input bindings"));
+            for (index, action) in Action::ALL.into_iter().enumerate() {
+                spawn_menu_item(parent, index, BindingItem::Action(action));
+            }
+            spawn_menu_item(parent, Action::ALL.len(), BindingItem::Back);
+            parent.spawn(menu_hint(
+                "Enter or click to add a binding, Delete to reset an action to its defaults.",
+            ));
+        });
+}
+
+// While listening, the next button pressed on any device is bound to the action.
+pub fn capture_binding(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut action_state: ResMut<ActionState>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(action) = rebinding.listening else {
+        return;
+    };
+
+    // The button that ends listening must not also trigger its action in the menu.
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        rebinding.listening = None;
+        action_state.clear();
+        return;
+    }
+
+    let binding = keyboard_input
+        .get_just_pressed()
+        .next()
+        .map(|key| InputBinding::Key(*key))
+        .or_else(|| {
+            mouse_input
+                .get_just_pressed()
+                .next()
+                .map(|button| InputBinding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepads
+                .iter()
+                .find_map(|gamepad| gamepad.get_just_pressed().next())
+                .map(|button| InputBinding::Gamepad(*button))
+        });
+
+    if let Some(binding) = binding {
+        settings.input.add(action, binding);
+        rebinding.listening = None;
+        action_state.clear();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn bindings_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    action_state: Res<ActionState>,
+    selection: Res<MenuSelection>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<(&BindingItem, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    if rebinding.listening.is_none() {
+        if action_state.just_pressed(Action::Back) {
+            next_menu_screen.set(MenuScreen::Settings);
+        }
+
+        let reset = keyboard_input.just_pressed(KeyCode::Delete)
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad.just_pressed(GamepadButton::North));
+        if let (true, Some(action)) = (reset, Action::ALL.get(selection.0)) {
+            settings.input.reset(*action);
+        }
+
+        for event in menu_events.read() {
+            let (Ok((item, _)), MenuInput::Activate) = (item_query.get(event.item), &event.input)
+            else {
+                continue;
+            };
+            match item {
+                BindingItem::Action(action) => rebinding.listening = Some(*action),
+                BindingItem::Back => next_menu_screen.set(MenuScreen::Settings),
+            }
+        }
+    }
+
+    for (item, children) in item_query.iter() {
+        let label = match item {
+            BindingItem::Action(action) if rebinding.listening == Some(*action) => {
+                format!("{action:?}: press any button... (Escape to cancel)")
+            }
+            BindingItem::Action(action) => {
+                let bindings = settings
+                    .input
+                    .get(*action)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{action:?}: {bindings}")
+            }
+            BindingItem::Back => "Back".to_string(),
+        };
+        set_item_label(children, &mut text_query, label);
+    }
+}
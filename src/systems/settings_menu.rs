@@ -0,0 +1,210 @@
+use bevy::prelude::*;
+
+use crate::{
+    components::SettingsItem,
+    constants::CAMERA_FOLLOW_FACTOR,
+    enums::{Action, AppState, ColorTheme, MenuInput, MenuScreen, PlayfieldScaling},
+    events::MenuEvent,
+    resources::ActionState,
+    settings::Settings,
+    systems::menu::{menu_hint, menu_root, menu_title, set_item_label, spawn_menu_item},
+};
+
+const VOLUME_STEP: f32 = 0.1;
+const FOLLOW_FACTORS: [f32; 4] = [0.1, CAMERA_FOLLOW_FACTOR, 0.25, 0.5];
+
+pub fn setup_settings_menu(mut commands: Commands) {
+    commands
+        .spawn(menu_root(MenuScreen::Settings))
+        .with_children(|parent| {
+            parent.spawn(menu_title("Settings"));
+            for (index, item) in [
+                SettingsItem::MasterVolume,
+                SettingsItem::MusicVolume,
+                SettingsItem::SfxVolume,
+                SettingsItem::Fullscreen,
+                SettingsItem::Vsync,
+                SettingsItem::PlayfieldScaling,
+                SettingsItem::ReducedMotion,
+                SettingsItem::ScreenShake,
+                SettingsItem::ZoomPulse,
+                SettingsItem::CameraFollow,
+                SettingsItem::FollowFactor,
+                SettingsItem::ColorTheme,
+                SettingsItem::ShowFps,
+                SettingsItem::InputBindings,
+                SettingsItem::Back,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                spawn_menu_item(parent, index, item);
+            }
+            parent.spawn(menu_hint(
+                "Up/Down to select, Left/Right or Enter to change.",
+            ));
+        });
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
+
+fn label(item: &SettingsItem, settings: &Settings) -> String {
+    let percent = |volume: f32| (volume * 100.0).round() as u32;
+    match item {
+        SettingsItem::MasterVolume => {
+            format!(
+                "Master volume: < {}% >",
+                percent(settings.audio.master_volume)
+            )
+        }
+        SettingsItem::MusicVolume => {
+            format!(
+                "Music volume: < {}% >",
+                percent(settings.audio.music_volume)
+            )
+        }
+        SettingsItem::SfxVolume => {
+            format!(
+                "Effects volume: < {}% >",
+                percent(settings.audio.sfx_volume)
+            )
+        }
+        SettingsItem::Fullscreen => format!("Fullscreen: {}", on_off(settings.display.fullscreen)),
+        SettingsItem::Vsync => format!("VSync: {}", on_off(settings.display.vsync)),
+        SettingsItem::PlayfieldScaling => {
+            format!("Wide screens: {:?}", settings.display.playfield_scaling)
+        }
+        SettingsItem::ReducedMotion => {
+            format!(
+                "Reduced motion: {}",
+                on_off(settings.display.reduced_motion)
+            )
+        }
+        SettingsItem::ScreenShake => {
+            format!("Screen shake: {}", on_off(settings.display.screen_shake))
+        }
+        SettingsItem::ZoomPulse => format!("Zoom pulse: {}", on_off(settings.display.zoom_pulse)),
+        SettingsItem::CameraFollow => {
+            format!("Camera follow: {}", on_off(settings.display.camera_follow))
+        }
+        SettingsItem::FollowFactor => {
+            format!(
+                "Follow strength: < {}% >",
+                percent(settings.display.follow_factor)
+            )
+        }
+        SettingsItem::ColorTheme => format!("Colour theme: {:?}", settings.display.color_theme),
+        SettingsItem::ShowFps => format!("Show FPS: {}", on_off(settings.display.show_fps)),
+        SettingsItem::InputBindings => "Input bindings".to_string(),
+        SettingsItem::Back => "Back".to_string(),
+    }
+}
+
+fn step_volume(volume: &mut f32, input: &MenuInput) {
+    let step = match input {
+        MenuInput::Decrease => -VOLUME_STEP,
+        MenuInput::Increase | MenuInput::Activate => VOLUME_STEP,
+    };
+    // Activating a full volume wraps around to mute.
+    *volume = if *volume >= 1.0 && matches!(input, MenuInput::Activate) {
+        0.0
+    } else {
+        ((*volume + step) * 10.0).round() / 10.0
+    }
+    .clamp(0.0, 1.0);
+}
+
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T, input: &MenuInput) -> T {
+    let index = options
+        .iter()
+        .position(|option| *option == current)
+        .unwrap_or(0);
+    let next = match input {
+        MenuInput::Decrease => (index + options.len() - 1) % options.len(),
+        MenuInput::Increase | MenuInput::Activate => (index + 1) % options.len(),
+    };
+    options[next]
+}
+
+pub fn settings_menu(
+    action_state: Res<ActionState>,
+    app_state: Res<State<AppState>>,
+    mut menu_events: EventReader<MenuEvent>,
+    item_query: Query<(&SettingsItem, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut settings: ResMut<Settings>,
+    mut next_menu_screen: ResMut<NextState<MenuScreen>>,
+) {
+    // The settings are reachable from both the main menu and the pause menu.
+    let previous_screen = match app_state.get() {
+        AppState::MainMenu => MenuScreen::Main,
+        AppState::Playing => MenuScreen::Pause,
+        AppState::Editing => MenuScreen::Closed,
+    };
+    if action_state.just_pressed(Action::Back) {
+        next_menu_screen.set(previous_screen);
+    }
+
+    for event in menu_events.read() {
+        let Ok((item, _)) = item_query.get(event.item) else {
+            continue;
+        };
+        let input = &event.input;
+
+        match item {
+            SettingsItem::MasterVolume => step_volume(&mut settings.audio.master_volume, input),
+            SettingsItem::MusicVolume => step_volume(&mut settings.audio.music_volume, input),
+            SettingsItem::SfxVolume => step_volume(&mut settings.audio.sfx_volume, input),
+            SettingsItem::Fullscreen => {
+                settings.display.fullscreen = !settings.display.fullscreen;
+            }
+            SettingsItem::Vsync => settings.display.vsync = !settings.display.vsync,
+            SettingsItem::PlayfieldScaling => {
+                settings.display.playfield_scaling = cycle(
+                    &[PlayfieldScaling::Letterbox, PlayfieldScaling::Extend],
+                    settings.display.playfield_scaling,
+                    input,
+                );
+            }
+            SettingsItem::ReducedMotion => {
+                settings.display.reduced_motion = !settings.display.reduced_motion;
+            }
+            SettingsItem::ScreenShake => {
+                settings.display.screen_shake = !settings.display.screen_shake;
+            }
+            SettingsItem::ZoomPulse => settings.display.zoom_pulse = !settings.display.zoom_pulse,
+            SettingsItem::CameraFollow => {
+                settings.display.camera_follow = !settings.display.camera_follow;
+            }
+            SettingsItem::FollowFactor => {
+                settings.display.follow_factor =
+                    cycle(&FOLLOW_FACTORS, settings.display.follow_factor, input);
+            }
+            SettingsItem::ColorTheme => {
+                settings.display.color_theme =
+                    cycle(&ColorTheme::ALL, settings.display.color_theme, input);
+            }
+            SettingsItem::ShowFps => settings.display.show_fps = !settings.display.show_fps,
+            SettingsItem::InputBindings => {
+                if let MenuInput::Activate = input {
+                    next_menu_screen.set(MenuScreen::Bindings);
+                }
+            }
+            SettingsItem::Back => {
+                if let MenuInput::Activate = input {
+                    next_menu_screen.set(previous_screen);
+                }
+            }
+        }
+    }
+
+    for (item, children) in item_query.iter() {
+        set_item_label(children, &mut text_query, label(item, &settings));
+    }
+}
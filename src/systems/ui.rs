@@ -1,6 +1,22 @@
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
 
-use crate::{components::ScoreText, resources::GameState};
+use crate::{
+    components::{
+        Copter, FloatingLabel, FpsText, InstructionsText, Score, ScoreBreakdownText, ScoreText,
+    },
+    constants::{FLOATING_LABEL_SPEED, FLOATING_LABEL_TIME, OBSTACLE_SPEED},
+    enums::{Action, AppState, GameMode},
+    events::NearMissEvent,
+    highscores::HighScores,
+    levels::Level,
+    pool::ColliderPool,
+    practice::Practice,
+    resources::{GameState, LevelProgress, ScoreWeights, SelectedGameMode},
+    settings::{binding_hint, Settings},
+};
 
 pub fn setup_ui(mut commands: Commands) {
     // TODO Score text.
@@ -21,9 +37,46 @@ pub fn setup_ui(mut commands: Commands) {
         ScoreText,
     ));
 
-    // TODO Instruction text.
+    // Score breakdown, only shown on game over.
     commands.spawn((
-        Text::new("Hold left click to fly up. Press R to restart when game over."),
+        Text::default(),
+        TextFont {
+            font_size: 26.0,
+            ..Default::default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            align_self: AlignSelf::Center,
+            justify_self: JustifySelf::Center,
+            ..Default::default()
+        },
+        Visibility::Hidden,
+        ScoreBreakdownText,
+    ));
+
+    // FPS counter and collider pool metrics, toggled in the settings.
+    commands.spawn((
+        Text::default(),
+        TextFont {
+            font_size: 18.0,
+            ..Default::default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(15.0),
+            ..Default::default()
+        },
+        Visibility::Hidden,
+        FpsText,
+    ));
+
+    // Filled in from the bindings by update_instructions_text.
+    commands.spawn((
+        Text::default(),
         TextFont {
             font_size: 30.0,
             ..Default::default()
@@ -34,22 +87,239 @@ pub fn setup_ui(mut commands: Commands) {
             top: Val::Px(5.0),
             ..Default::default()
         },
+        InstructionsText,
     ));
 }
 
+pub fn update_instructions_text(
+    settings: Res<Settings>,
+    mut text_query: Query<&mut Text, With<InstructionsText>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        return;
+    };
+
+    let hint = |action| binding_hint(&settings, action);
+    text.0 = format!(
+        "Hold {} to fly up. Press {} to restart when game over, {} to pause.",
+        hint(Action::Lift),
+        hint(Action::Restart),
+        hint(Action::Pause),
+    );
+}
+
 pub fn update_score(
     time: Res<Time>,
-    mut game_state: ResMut<GameState>,
-    mut score_query: Query<(&mut Text, &Node), With<ScoreText>>,
+    game_state: Res<GameState>,
+    mut copter_query: Query<(&Copter, &mut Score)>,
 ) {
     if game_state.game_over {
         return;
     }
-    // println!("Time elapsed since last update: {}", time.delta_secs());
-    game_state.score += time.delta_secs();
+
+    for (copter, mut score) in copter_query.iter_mut() {
+        // Distance follows the scroll of the world, so it matches what the gamer actually flew.
+        if !copter.crashed() {
+            score.distance += OBSTACLE_SPEED * time.delta_secs();
+        }
+    }
+}
+
+pub fn update_score_text(
+    score_weights: Res<ScoreWeights>,
+    copter_query: Query<(&Copter, &Score)>,
+    mut score_query: Query<(&mut Text, &Node), With<ScoreText>>,
+) {
+    let mut scores: Vec<_> = copter_query.iter().collect();
+    scores.sort_by_key(|(copter, _)| copter.player);
 
     if let Ok((mut score_text, _)) = score_query.get_single_mut() {
-        score_text.0 = format!("Score: {}", game_state.score as u32);
-        // println!("{}", score_text.0);
+        score_text.0 = match scores.as_slice() {
+            [(_, score)] => format!("Score: {}", score.total(&score_weights)),
+            _ => scores
+                .iter()
+                .map(|(copter, score)| {
+                    format!("{}: {}", copter.player.name(), score.total(&score_weights))
+                })
+                .collect::<Vec<_>>()
+                .join("   "),
+        };
     }
 }
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_score_breakdown(
+    app_state: Res<State<AppState>>,
+    game_state: Res<GameState>,
+    copter_query: Query<(&Copter, &Score)>,
+    score_weights: Res<ScoreWeights>,
+    high_scores: Res<HighScores>,
+    selected_mode: Res<SelectedGameMode>,
+    level: Option<Res<Level>>,
+    level_progress: Res<LevelProgress>,
+    practice: Res<Practice>,
+    mut breakdown_query: Query<(&mut Text, &mut Visibility), With<ScoreBreakdownText>>,
+) {
+    let Ok((mut breakdown_text, mut visibility)) = breakdown_query.get_single_mut() else {
+        return;
+    };
+
+    if !game_state.game_over || *app_state.get() != AppState::Playing {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+
+    let mut scores: Vec<_> = copter_query.iter().collect();
+    scores.sort_by_key(|(copter, _)| copter.player);
+    let [(_, score)] = scores.as_slice() else {
+        breakdown_text.0 = versus_result(&scores, &score_weights);
+        return;
+    };
+
+    // Levels are finished rather than ranked.
+    if let Some(level) = level {
+        let outcome = if level_progress.completed {
+            format!("{} complete!", level.name)
+        } else {
+            format!(
+                "Checkpoints: {}/{}",
+                level_progress.checkpoints_reached,
+                level.checkpoints.len()
+            )
+        };
+        breakdown_text.0 = format!(
+            "{outcome}\n\nDistance: {} m\nObstacles passed: {}\nNear-miss bonus: {}\n\nTotal: {}",
+            score.meters() as u32,
+            score.obstacles_passed,
+            score.near_miss_points,
+            score.total(&score_weights),
+        );
+        return;
+    }
+
+    if selected_mode.0 == GameMode::Practice {
+        let back_to = if practice.has_checkpoint() {
+            "the last checkpoint"
+        } else {
+            "the start"
+        };
+        breakdown_text.0 = format!(
+            "Back to {back_to} in a moment...\n\nDistance: {} m\nObstacles passed: {}\nNear-miss bonus: {}",
+            score.meters() as u32,
+            score.obstacles_passed,
+            score.near_miss_points,
+        );
+        return;
+    }
+
+    let best = high_scores.best(selected_mode.0).unwrap_or_default();
+    breakdown_text.0 = format!(
+        "Distance: {} m\nObstacles passed: {}\nNear-miss bonus: {}\n\nTotal: {}\nBest: {}",
+        score.meters() as u32,
+        score.obstacles_passed,
+        score.near_miss_points,
+        score.total(&score_weights),
+        best,
+    );
+}
+
+// The copter that flew the furthest wins, whatever the bonus points say.
+fn versus_result(scores: &[(&Copter, &Score)], score_weights: &ScoreWeights) -> String {
+    let furthest = scores
+        .iter()
+        .map(|(_, score)| score.distance)
+        .fold(0.0, f32::max);
+    let winners: Vec<_> = scores
+        .iter()
+        .filter(|(_, score)| score.distance >= furthest)
+        .collect();
+
+    let mut result: Vec<_> = scores
+        .iter()
+        .map(|(copter, score)| {
+            format!(
+                "{}: {} m, score {}",
+                copter.player.name(),
+                score.meters() as u32,
+                score.total(score_weights)
+            )
+        })
+        .collect();
+    result.push(String::new());
+    result.push(match winners.as_slice() {
+        [(copter, _)] => format!("{} wins!", copter.player.name()),
+        _ => "Draw!".to_string(),
+    });
+    result.join("\n")
+}
+
+// Pop a "+N" label above the copter for every near-miss bonus.
+pub fn spawn_near_miss_labels(
+    mut commands: Commands,
+    mut near_miss_events: EventReader<NearMissEvent>,
+) {
+    for near_miss in near_miss_events.read() {
+        commands.spawn((
+            Text2d::new(format!("+{}", near_miss.points)),
+            TextFont {
+                font_size: 28.0,
+                ..Default::default()
+            },
+            TextColor(Color::srgb(1.0, 0.85, 0.3)),
+            Transform::from_translation(near_miss.position + Vec3::new(0.0, 40.0, 2.0)),
+            FloatingLabel {
+                timer: Timer::from_seconds(FLOATING_LABEL_TIME, TimerMode::Once),
+            },
+        ));
+    }
+}
+
+// Labels drift upwards and fade out, then despawn.
+pub fn floating_labels(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut label_query: Query<(Entity, &mut FloatingLabel, &mut Transform, &mut TextColor)>,
+) {
+    for (entity, mut label, mut transform, mut text_color) in label_query.iter_mut() {
+        label.timer.tick(time.delta());
+        transform.translation.y += FLOATING_LABEL_SPEED * time.delta_secs();
+        text_color.0.set_alpha(1.0 - label.timer.fraction());
+
+        if label.timer.finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub fn update_fps_text(
+    settings: Res<Settings>,
+    diagnostics: Res<DiagnosticsStore>,
+    pool: Res<ColliderPool>,
+    mut fps_query: Query<(&mut Text, &mut Visibility), With<FpsText>>,
+) {
+    let Ok((mut fps_text, mut visibility)) = fps_query.get_single_mut() else {
+        return;
+    };
+
+    if !settings.display.show_fps {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+
+    *visibility = Visibility::Inherited;
+    let fps = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    fps_text.0 = format!(
+        "FPS: {fps:.0}\nColliders: {} allocated, {} reused, {} parked",
+        pool.metrics.allocated,
+        pool.metrics.reused,
+        pool.parked()
+    );
+}
@@ -0,0 +1,204 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    components::Wall,
+    resources::{BorderTileCurrentHeight, Playfield},
+    settings::Settings,
+};
+
+/*
+    Terrain:
+        The walls of the cave are a height-field: samples of how far the top
+        wall hangs down and the bottom wall stands up, ordered by x, with the
+        walls running straight from one sample to the next. New samples are
+        pushed on the right as the cave is generated, the whole field scrolls
+        left with the world, and samples are dropped once the one after them
+        left the screen too, so that the walls always reach the left edge.
+
+        Collisions query the height-field over the copter's width directly,
+        and each wall is drawn as a single mesh following it.
+*/
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TerrainSample {
+    pub x: f32,
+    pub top: f32,
+    pub bottom: f32,
+}
+
+#[derive(Resource, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Terrain {
+    samples: VecDeque<TerrainSample>,
+}
+
+impl Terrain {
+    pub fn samples(&self) -> impl Iterator<Item = &TerrainSample> {
+        self.samples.iter()
+    }
+
+    pub fn last(&self) -> Option<&TerrainSample> {
+        self.samples.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    // Samples are expected to come in from left to right.
+    pub fn push(&mut self, x: f32, heights: &BorderTileCurrentHeight) {
+        self.samples.push_back(TerrainSample {
+            x,
+            top: heights.top_border,
+            bottom: heights.bottom_border,
+        });
+    }
+
+    pub fn scroll(&mut self, distance: f32, left_edge: f32) {
+        for sample in self.samples.iter_mut() {
+            sample.x -= distance;
+        }
+        while self.samples.get(1).is_some_and(|next| next.x < left_edge) {
+            self.samples.pop_front();
+        }
+    }
+
+    // Heights of the walls at x, which are level beyond the outermost samples.
+    pub fn heights_at(&self, x: f32) -> BorderTileCurrentHeight {
+        let Some(first) = self.samples.front() else {
+            return BorderTileCurrentHeight {
+                top_border: 0.0,
+                bottom_border: 0.0,
+            };
+        };
+        let next = self.samples.partition_point(|sample| sample.x <= x);
+        let sample = match (next.checked_sub(1), self.samples.get(next)) {
+            (None, _) => *first,
+            (Some(previous), None) => self.samples[previous],
+            (Some(previous), Some(next)) => {
+                let previous = self.samples[previous];
+                let t = (x - previous.x) / (next.x - previous.x);
+                TerrainSample {
+                    x,
+                    top: previous.top.lerp(next.top, t),
+                    bottom: previous.bottom.lerp(next.bottom, t),
+                }
+            }
+        };
+
+        BorderTileCurrentHeight {
+            top_border: sample.top,
+            bottom_border: sample.bottom,
+        }
+    }
+
+    // Highest the walls reach anywhere from one x to the other.
+    pub fn max_heights(&self, from: f32, to: f32) -> BorderTileCurrentHeight {
+        // Walls run straight between samples, so they peak at a sample or at either end.
+        let mut heights = self.heights_at(from);
+        let end = self.heights_at(to);
+        heights.top_border = heights.top_border.max(end.top_border);
+        heights.bottom_border = heights.bottom_border.max(end.bottom_border);
+
+        // Only the samples strictly between both ends, found by bisecting for each end.
+        let first = self.samples.partition_point(|sample| sample.x <= from);
+        let last = self.samples.partition_point(|sample| sample.x < to);
+        for sample in self.samples.range(first..last.max(first)) {
+            heights.top_border = heights.top_border.max(sample.top);
+            heights.bottom_border = heights.bottom_border.max(sample.bottom);
+        }
+        heights
+    }
+}
+
+// Both walls start out empty, the meshes follow the terrain from the first frame on.
+pub fn spawn_walls(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let material = materials.add(settings.display.color_theme.wall());
+
+    for top in [true, false] {
+        let mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, Vec::<[f32; 3]>::new())
+        .with_inserted_indices(Indices::U32(Vec::new()));
+        commands.spawn((
+            Mesh2d(meshes.add(mesh)),
+            MeshMaterial2d(material.clone()),
+            Transform::from_xyz(0.0, 0.0, 1.0),
+            Wall { top },
+        ));
+    }
+}
+
+/*
+    Every sample makes a column of two vertices, the lower one first, from
+    the edge of the playfield to the tip of the wall. Neighbouring columns
+    are joined by two triangles.
+*/
+pub fn draw_walls(
+    terrain: Res<Terrain>,
+    playfield: Res<Playfield>,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    wall_query: Query<(&Wall, &Mesh2d, &MeshMaterial2d<ColorMaterial>)>,
+) {
+    for (wall, mesh, material) in wall_query.iter() {
+        if settings.is_changed() {
+            if let Some(material) = materials.get_mut(&material.0) {
+                material.color = settings.display.color_theme.wall();
+            }
+        }
+        if !terrain.is_changed() && !playfield.is_changed() {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+
+        let half_height = playfield.half_height();
+        let positions: Vec<[f32; 3]> = terrain
+            .samples()
+            .flat_map(|sample| {
+                let (lower, upper) = if wall.top {
+                    (half_height - sample.top, half_height)
+                } else {
+                    (-half_height, -half_height + sample.bottom)
+                };
+                [[sample.x, lower, 0.0], [sample.x, upper, 0.0]]
+            })
+            .collect();
+        let columns = (positions.len() / 2) as u32;
+        let indices: Vec<u32> = (1..columns)
+            .flat_map(|column| {
+                let (lower, upper) = (column * 2, column * 2 + 1);
+                let (previous_lower, previous_upper) = (lower - 2, upper - 2);
+                [
+                    previous_lower,
+                    lower,
+                    upper,
+                    previous_lower,
+                    upper,
+                    previous_upper,
+                ]
+            })
+            .collect();
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_indices(Indices::U32(indices));
+    }
+}
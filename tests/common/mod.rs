@@ -0,0 +1,242 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::system::RunSystemOnce, prelude::*, state::app::StatesPlugin, time::TimeUpdateStrategy,
+};
+use copter::{
+    broadphase::update_broad_phase,
+    components::{Copter, Obstacle, Score},
+    constants::SIMULATION_TIMESTEP,
+    enums::{Action, AppState, CrashCause, Player},
+    resources::{ActionState, BorderTileCurrentHeight, GameRng, GameState},
+    settings::Settings,
+    simulation::{copter_bundle, SimulationPlugin},
+    systems::collision::collision_detection,
+    terrain::{Terrain, TerrainSample},
+};
+
+/*
+    A world of the simulation on its own, stepped tick by tick. Tests hold
+    actions down instead of pressing buttons, place the copter, obstacles
+    and walls where they need them, and read back what happened.
+*/
+pub struct TestWorld {
+    pub app: App,
+}
+
+impl TestWorld {
+    // A run on the seed, with the copter at its start and the border lining the playfield.
+    pub fn new(seed: u64) -> Self {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin))
+            .add_plugins(SimulationPlugin)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+                SIMULATION_TIMESTEP,
+            )))
+            .insert_state(AppState::Playing)
+            .insert_resource(GameRng::seeded(seed));
+        // Startup spawns the copter and the border.
+        app.update();
+        Self { app }
+    }
+
+    // Random obstacles stop appearing and the walls stop growing, so that only what a test places is there.
+    pub fn without_spawning(mut self) -> Self {
+        let mut game_state = self.app.world_mut().resource_mut::<GameState>();
+        game_state.obstacle_timer.pause();
+        game_state.bordertile_timer.pause();
+        self
+    }
+
+    pub fn world(&mut self) -> &mut World {
+        self.app.world_mut()
+    }
+
+    // Advances the world by a single step of the fixed timestep.
+    pub fn tick(&mut self) {
+        let ticked = self.world().resource::<Time<Fixed>>().elapsed();
+        while self.world().resource::<Time<Fixed>>().elapsed() == ticked {
+            self.app.update();
+        }
+    }
+
+    pub fn ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    pub fn seconds(&mut self, seconds: f32) {
+        self.ticks((seconds / SIMULATION_TIMESTEP).round() as u32);
+    }
+
+    // Advances the world by whole ticks, with frames of another length than a tick.
+    pub fn seconds_in_frames(&mut self, seconds: f32, frame_time: Duration) {
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(frame_time));
+        let timestep = self.world().resource::<Time<Fixed>>().timestep();
+        let until = self.world().resource::<Time<Fixed>>().elapsed()
+            + timestep * (seconds / SIMULATION_TIMESTEP).round() as u32;
+        while self.world().resource::<Time<Fixed>>().elapsed() < until {
+            self.app.update();
+        }
+        self.app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    }
+
+    pub fn hold(&mut self, action: Action) {
+        self.world().resource_mut::<ActionState>().press(action);
+    }
+
+    pub fn release(&mut self, action: Action) {
+        self.world().resource_mut::<ActionState>().release(action);
+    }
+
+    // Presses an action for a single frame.
+    pub fn tap(&mut self, action: Action) {
+        self.world()
+            .resource_mut::<ActionState>()
+            .just_press(action);
+        self.app.update();
+        self.release(action);
+    }
+
+    // Checks for collisions right away, with everything where it was placed.
+    pub fn detect_collisions(&mut self) {
+        self.world()
+            .run_system_once(update_broad_phase)
+            .expect("the broad-phase updates on the test world");
+        self.world()
+            .run_system_once(collision_detection)
+            .expect("collision detection runs on the test world");
+    }
+
+    pub fn copter(&mut self) -> (Copter, Vec3) {
+        let world = self.world();
+        let (copter, transform) = world.query::<(&Copter, &Transform)>().single(world);
+        (copter.clone(), transform.translation)
+    }
+
+    pub fn score(&mut self) -> Score {
+        let world = self.world();
+        world.query::<&Score>().single(world).clone()
+    }
+
+    pub fn place_copter(&mut self, y: f32, velocity: f32) {
+        let world = self.world();
+        let (mut copter, mut transform) = world
+            .query::<(&mut Copter, &mut Transform)>()
+            .single_mut(world);
+        copter.velocity = velocity;
+        transform.translation.y = y;
+    }
+
+    // Another copter for the same run, as in versus.
+    pub fn spawn_copter(&mut self, player: Player, y: f32) {
+        let settings = Settings::default();
+        let mut copter = self.world().spawn(copter_bundle(player, 2, &settings));
+        copter
+            .get_mut::<Transform>()
+            .expect("copters have a transform")
+            .translation
+            .y = y;
+    }
+
+    pub fn spawn_obstacle(&mut self, position: Vec2, size: Vec2) -> Entity {
+        self.world()
+            .spawn((
+                Sprite {
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                Transform::from_translation(position.extend(1.0)),
+                Obstacle,
+            ))
+            .id()
+    }
+
+    // Replaces the walls with a height-field of the samples, ordered by x.
+    pub fn set_walls(&mut self, samples: &[TerrainSample]) {
+        let mut terrain = Terrain::default();
+        for sample in samples {
+            terrain.push(
+                sample.x,
+                &BorderTileCurrentHeight {
+                    top_border: sample.top,
+                    bottom_border: sample.bottom,
+                },
+            );
+        }
+        self.world().insert_resource(terrain);
+    }
+
+    // Walls of the same heights all along the playfield.
+    pub fn flat_walls(&mut self, top: f32, bottom: f32) {
+        self.set_walls(&[TerrainSample {
+            x: 0.0,
+            top,
+            bottom,
+        }]);
+    }
+
+    pub fn clear_obstacles(&mut self) {
+        self.despawn_all::<Obstacle>();
+    }
+
+    fn despawn_all<T: Component>(&mut self) {
+        let world = self.world();
+        let entities: Vec<_> = world
+            .query_filtered::<Entity, With<T>>()
+            .iter(world)
+            .collect();
+        for entity in entities {
+            world.despawn(entity);
+        }
+    }
+
+    pub fn obstacles(&mut self) -> Vec<Vec3> {
+        self.positions::<Obstacle>()
+    }
+
+    pub fn terrain(&mut self) -> Vec<TerrainSample> {
+        self.world()
+            .resource::<Terrain>()
+            .samples()
+            .copied()
+            .collect()
+    }
+
+    fn positions<T: Component>(&mut self) -> Vec<Vec3> {
+        let world = self.world();
+        let mut positions: Vec<_> = world
+            .query_filtered::<&Transform, With<T>>()
+            .iter(world)
+            .map(|transform| transform.translation)
+            .collect();
+        positions.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        positions
+    }
+
+    // Parked entities still exist, but are no obstacles anymore.
+    pub fn is_obstacle(&mut self, entity: Entity) -> bool {
+        self.world()
+            .get_entity(entity)
+            .is_ok_and(|entity| entity.contains::<Obstacle>())
+    }
+
+    pub fn game_over(&mut self) -> bool {
+        self.world().resource::<GameState>().game_over
+    }
+
+    pub fn crash_cause(&mut self) -> Option<CrashCause> {
+        self.copter().0.crash_cause
+    }
+
+    // What every copter crashed into, by player.
+    pub fn crash_causes(&mut self) -> Vec<Option<CrashCause>> {
+        let world = self.world();
+        let mut copters: Vec<_> = world.query::<&Copter>().iter(world).collect();
+        copters.sort_by_key(|copter| copter.player.0);
+        copters.iter().map(|copter| copter.crash_cause).collect()
+    }
+}
@@ -0,0 +1,420 @@
+mod common;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use common::TestWorld;
+use copter::{
+    bot::BotPilot,
+    constants::{
+        BORDERTILE_SPAWN_TIME, BORDERTILE_SPEED, BORDERTILE_WIDTH, COPTER_SIZE, COPTER_START_X,
+        NEAR_MISS_MARGIN, OBSTACLE_HEIGHT, OBSTACLE_SPAWN_TIME, OBSTACLE_SPEED, OBSTACLE_WIDTH,
+        PLAYFIELD_HEIGHT, PLAYFIELD_WIDTH,
+    },
+    editor::rebuild_course,
+    enums::{Action, CrashCause, Player},
+    levels::{Level, ObstaclePlacement},
+    pool::ColliderPool,
+    resources::{LevelProgress, Playfield},
+    snapshot::SimulationSnapshot,
+    systems::autopilot::Autopilot,
+    terrain::TerrainSample,
+};
+
+const OBSTACLE_SIZE: Vec2 = Vec2::new(OBSTACLE_WIDTH, OBSTACLE_HEIGHT);
+
+// A sample of the walls with only the top one.
+fn wall(x: f32, top: f32) -> TerrainSample {
+    TerrainSample {
+        x,
+        top,
+        bottom: 0.0,
+    }
+}
+
+#[test]
+fn copter_falls_without_lift() {
+    let mut world = TestWorld::new(1).without_spawning();
+    world.ticks(10);
+
+    let (copter, position) = world.copter();
+    assert!(position.y < 0.0);
+    assert!(copter.velocity < 0.0);
+}
+
+#[test]
+fn lift_raises_copter() {
+    let mut world = TestWorld::new(1).without_spawning();
+    world.hold(Action::Lift);
+    world.ticks(10);
+
+    let (copter, position) = world.copter();
+    assert!(position.y > 0.0);
+    assert!(copter.velocity > 0.0);
+}
+
+#[test]
+fn copter_at_obstacle_collides() {
+    let mut world = TestWorld::new(1).without_spawning();
+    world.spawn_obstacle(Vec2::new(COPTER_START_X, 0.0), OBSTACLE_SIZE);
+    world.detect_collisions();
+
+    assert!(world.game_over());
+    assert_eq!(world.crash_cause(), Some(CrashCause::Obstacle));
+}
+
+// Touching counts on the y-axis, but not on the x-axis.
+#[test]
+fn obstacle_touching_copter_from_above_collides() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let y = (COPTER_SIZE.y + OBSTACLE_HEIGHT) * 0.5;
+    world.spawn_obstacle(Vec2::new(COPTER_START_X, y), OBSTACLE_SIZE);
+    world.detect_collisions();
+
+    assert!(world.game_over());
+}
+
+#[test]
+fn obstacle_touching_copter_from_the_side_does_not_collide() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let x = COPTER_START_X + (COPTER_SIZE.x + OBSTACLE_WIDTH) * 0.5;
+    world.spawn_obstacle(Vec2::new(x, 0.0), OBSTACLE_SIZE);
+    world.detect_collisions();
+
+    assert!(!world.game_over());
+}
+
+#[test]
+fn copter_flies_into_obstacle_ahead() {
+    let mut world = TestWorld::new(1).without_spawning();
+    world.spawn_obstacle(Vec2::new(COPTER_START_X + 200.0, 0.0), OBSTACLE_SIZE);
+    world.place_copter(0.0, 0.0);
+    world.seconds(0.5);
+
+    assert!(world.game_over());
+    assert_eq!(world.crash_cause(), Some(CrashCause::Obstacle));
+}
+
+// Grazing an obstacle scores once it is passed.
+#[test]
+fn passing_close_to_an_obstacle_is_a_near_miss() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let above = (COPTER_SIZE.y + OBSTACLE_HEIGHT) * 0.5 + NEAR_MISS_MARGIN * 0.5;
+    world.spawn_obstacle(Vec2::new(COPTER_START_X, above), OBSTACLE_SIZE);
+    for _ in 0..30 {
+        world.place_copter(0.0, 0.0);
+        world.tick();
+    }
+
+    let score = world.score();
+    assert!(!world.game_over());
+    assert_eq!(score.obstacles_passed, 1);
+    assert!(score.near_miss_points > 0);
+}
+
+// Unlike obstacles, walls only collide once the copter pokes into them.
+#[test]
+fn copter_touching_wall_does_not_collide() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let height = 100.0;
+    world.flat_walls(height, 0.0);
+    world.place_copter(PLAYFIELD_HEIGHT * 0.5 - height - COPTER_SIZE.y * 0.5, 0.0);
+    world.detect_collisions();
+
+    assert!(!world.game_over());
+}
+
+#[test]
+fn copter_overlapping_wall_collides() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let height = 100.0;
+    world.flat_walls(0.0, height);
+    world.place_copter(
+        -PLAYFIELD_HEIGHT * 0.5 + height + COPTER_SIZE.y * 0.5 - 1.0,
+        0.0,
+    );
+    world.detect_collisions();
+
+    assert!(world.game_over());
+    assert_eq!(world.crash_cause(), Some(CrashCause::BottomBorder));
+}
+
+// Every copter of a race remembers what it crashed into itself.
+#[test]
+fn copters_crash_into_their_own_causes() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let above = COPTER_SIZE.y + 10.0;
+    world.spawn_copter(Player::TWO, above);
+    let obstacle_bottom = above + COPTER_SIZE.y * 0.5 - 1.0;
+    world.spawn_obstacle(
+        Vec2::new(COPTER_START_X, obstacle_bottom + OBSTACLE_HEIGHT * 0.5),
+        OBSTACLE_SIZE,
+    );
+    world.flat_walls(0.0, PLAYFIELD_HEIGHT * 0.5 - COPTER_SIZE.y * 0.5 + 1.0);
+    world.detect_collisions();
+
+    assert!(world.game_over());
+    assert_eq!(
+        world.crash_causes(),
+        vec![Some(CrashCause::BottomBorder), Some(CrashCause::Obstacle)]
+    );
+}
+
+// Only the walls within the width of the copter are checked.
+#[test]
+fn wall_rising_beside_copter_does_not_collide() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let copter_right = COPTER_START_X + COPTER_SIZE.x * 0.5;
+    world.set_walls(&[
+        wall(copter_right, 0.0),
+        wall(copter_right + BORDERTILE_WIDTH, PLAYFIELD_HEIGHT * 0.5),
+    ]);
+    world.detect_collisions();
+
+    assert!(!world.game_over());
+}
+
+// Walls run straight between samples, so the copter can crash into one between them.
+#[test]
+fn wall_collides_between_samples() {
+    let copter_right = COPTER_START_X + COPTER_SIZE.x * 0.5;
+    let slope = [
+        wall(COPTER_START_X - 100.0, 0.0),
+        wall(COPTER_START_X + 100.0, 200.0),
+    ];
+    // The top wall comes down a pixel for every pixel to the right.
+    let height = copter_right - slope[0].x;
+
+    for (clearance, crashed) in [(-1.0, true), (0.0, false)] {
+        let mut world = TestWorld::new(1).without_spawning();
+        world.set_walls(&slope);
+        world.place_copter(
+            PLAYFIELD_HEIGHT * 0.5 - height - COPTER_SIZE.y * 0.5 - clearance,
+            0.0,
+        );
+        world.detect_collisions();
+
+        assert_eq!(world.game_over(), crashed);
+    }
+}
+
+#[test]
+fn walls_are_dropped_after_leaving_the_screen() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let left_edge = -PLAYFIELD_WIDTH * 0.5;
+    world.set_walls(&[
+        wall(left_edge - 100.0, 75.0),
+        wall(left_edge + 1.0, 75.0),
+        wall(0.0, 75.0),
+    ]);
+    world.hold(Action::Lift);
+    world.tick();
+
+    // The walls still reach the edge of the screen.
+    let terrain = world.terrain();
+    assert_eq!(terrain.len(), 2);
+    assert!(terrain[0].x < left_edge);
+}
+
+#[test]
+fn obstacles_are_parked_after_leaving_the_screen() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let left_edge = -PLAYFIELD_WIDTH * 0.5 - OBSTACLE_WIDTH;
+    let leaving = world.spawn_obstacle(Vec2::new(left_edge + 1.0, 300.0), OBSTACLE_SIZE);
+    let staying = world.spawn_obstacle(Vec2::new(0.0, 300.0), OBSTACLE_SIZE);
+    world.hold(Action::Lift);
+    world.tick();
+
+    assert!(!world.is_obstacle(leaving));
+    assert!(world.is_obstacle(staying));
+}
+
+// New walls keep coming in on the right while the old ones leave on the left.
+#[test]
+fn walls_keep_coming_without_piling_up() {
+    let mut world = TestWorld::new(1);
+    let initial = world.terrain().len();
+    for _ in 0..10 {
+        world.hold(Action::Lift);
+        world.ticks(12);
+        world.release(Action::Lift);
+        world.ticks(12);
+        world.place_copter(0.0, 0.0);
+        world.clear_obstacles();
+    }
+
+    let terrain = world.terrain();
+    assert!(!world.game_over());
+    assert!(terrain.len() <= initial);
+    let newest = terrain.last().expect("walls are on screen");
+    assert!(newest.x >= PLAYFIELD_WIDTH * 0.5 - BORDERTILE_SPEED * BORDERTILE_SPAWN_TIME);
+}
+
+#[test]
+fn walls_stay_in_order_when_the_playfield_shrinks() {
+    let mut world = TestWorld::new(1);
+    world.world().resource_mut::<BotPilot>().0 = Some(Box::new(Autopilot));
+    world.world().resource_mut::<Playfield>().width = PLAYFIELD_WIDTH * 2.0;
+    world.seconds(1.0);
+    world.world().resource_mut::<Playfield>().width = PLAYFIELD_WIDTH;
+    world.seconds(1.0);
+
+    let terrain = world.terrain();
+    assert!(!world.game_over());
+    assert!(terrain.windows(2).all(|pair| pair[0].x < pair[1].x));
+}
+
+#[test]
+fn restart_clears_all_obstacles() {
+    let mut world = TestWorld::new(1).without_spawning();
+    for x in [-100.0, 100.0, 300.0] {
+        world.spawn_obstacle(Vec2::new(x, 300.0), OBSTACLE_SIZE);
+    }
+    world.spawn_obstacle(Vec2::new(COPTER_START_X, 0.0), OBSTACLE_SIZE);
+    world.tick();
+    assert!(world.game_over());
+
+    world.tap(Action::Restart);
+
+    assert!(!world.game_over());
+    assert!(world.obstacles().is_empty());
+    assert_eq!(world.score().distance, 0.0);
+    assert_eq!(world.copter().1.y, 0.0);
+}
+
+// Obstacles that flew past are parked and brought back as the next ones.
+#[test]
+fn passed_obstacles_are_reused() {
+    let mut world = TestWorld::new(1);
+    world.world().resource_mut::<BotPilot>().0 = Some(Box::new(Autopilot));
+    world.seconds(10.0);
+
+    // An obstacle crosses the playfield until it is parked, while new ones keep being spawned.
+    // One more may be spawned before the oldest is back in the pool.
+    let crossing = (PLAYFIELD_WIDTH + OBSTACLE_WIDTH * 1.5) / OBSTACLE_SPEED;
+    let on_screen = (crossing / OBSTACLE_SPAWN_TIME).ceil() as u64 + 1;
+    let metrics = world.world().resource::<ColliderPool>().metrics;
+    assert!(!world.game_over());
+    assert!(metrics.reused > 0);
+    assert!(metrics.allocated <= on_screen);
+}
+
+// Restarting only takes the gamer back once the run is over.
+#[test]
+fn restart_is_ignored_while_flying() {
+    let mut world = TestWorld::new(1).without_spawning();
+    world.spawn_obstacle(Vec2::new(300.0, 300.0), OBSTACLE_SIZE);
+    world.tap(Action::Restart);
+    world.tick();
+
+    assert_eq!(world.obstacles().len(), 1);
+}
+
+#[test]
+fn same_seed_flies_through_the_same_course() {
+    let mut first = TestWorld::new(42);
+    let mut second = TestWorld::new(42);
+    for world in [&mut first, &mut second] {
+        world.hold(Action::Lift);
+        world.ticks(15);
+        world.release(Action::Lift);
+        world.seconds(2.0);
+        world.place_copter(0.0, 0.0);
+        world.seconds(2.0);
+    }
+
+    assert_eq!(first.obstacles(), second.obstacles());
+    assert_eq!(first.terrain(), second.terrain());
+}
+
+// The world only moves on the fixed timestep, however the frames fall.
+#[test]
+fn same_seed_flies_through_the_same_course_at_any_frame_rate() {
+    let mut worlds = [TestWorld::new(42), TestWorld::new(42), TestWorld::new(42)];
+    for world in worlds.iter_mut() {
+        world.world().resource_mut::<BotPilot>().0 = Some(Box::new(Autopilot));
+    }
+    let timestep = worlds[0].world().resource::<Time<Fixed>>().timestep();
+    worlds[0].seconds(3.0);
+    worlds[1].seconds_in_frames(3.0, timestep * 3);
+    worlds[2].seconds_in_frames(3.0, timestep / 4);
+
+    let [first, rest @ ..] = &mut worlds;
+    assert!(!first.obstacles().is_empty());
+    for world in rest {
+        assert_eq!(first.copter().1, world.copter().1);
+        assert_eq!(first.obstacles(), world.obstacles());
+        assert_eq!(first.terrain(), world.terrain());
+    }
+}
+
+#[test]
+fn snapshot_restores_the_world() {
+    let mut world = TestWorld::new(7);
+    world.seconds(0.5);
+    let snapshot = SimulationSnapshot::take(world.world());
+    let obstacles = world.obstacles();
+    let terrain = world.terrain();
+    let copter = world.copter().1;
+
+    world.clear_obstacles();
+    world.set_walls(&[]);
+    world.seconds(0.3);
+    snapshot.restore(world.world());
+
+    assert_eq!(world.obstacles(), obstacles);
+    assert_eq!(world.terrain(), terrain);
+    assert_eq!(world.copter().1, copter);
+}
+
+#[test]
+fn restoring_a_snapshot_reuses_the_obstacles() {
+    let mut world = TestWorld::new(7).without_spawning();
+    world.spawn_obstacle(Vec2::new(100.0, 300.0), OBSTACLE_SIZE);
+    world.spawn_obstacle(Vec2::new(300.0, 300.0), OBSTACLE_SIZE);
+    let snapshot = SimulationSnapshot::take(world.world());
+    snapshot.restore(world.world());
+    snapshot.restore(world.world());
+
+    let metrics = world.world().resource::<ColliderPool>().metrics;
+    assert_eq!(world.obstacles().len(), 2);
+    assert_eq!(metrics.allocated, 0);
+    assert_eq!(metrics.reused, 4);
+}
+
+#[test]
+fn level_ends_at_the_finish_line() {
+    let mut world = TestWorld::new(1);
+    let mut level = Level::new("Short hop".to_string());
+    level.finish = 300.0;
+    world.world().insert_resource(level);
+    world.seconds(0.6);
+
+    assert!(world.world().resource::<LevelProgress>().completed);
+    assert!(world.game_over());
+}
+
+// Scrolling the editor moves the obstacles already there, rather than trading them for new ones.
+#[test]
+fn scrolling_the_editor_keeps_the_obstacles() {
+    let mut world = TestWorld::new(1).without_spawning();
+    let mut level = Level::new("Scrolling".to_string());
+    for distance in [400.0, 700.0] {
+        level.obstacles.push(ObstaclePlacement {
+            distance,
+            y: 0.0,
+            size: OBSTACLE_SIZE,
+        });
+    }
+    world.world().insert_resource(level);
+    for _ in 0..5 {
+        world.world().resource_mut::<LevelProgress>().distance += 10.0;
+        world
+            .world()
+            .run_system_once(rebuild_course)
+            .expect("the course is rebuilt on the test world");
+    }
+
+    let metrics = world.world().resource::<ColliderPool>().metrics;
+    assert_eq!(world.obstacles().len(), 2);
+    assert_eq!(metrics.allocated, 2);
+    assert_eq!(metrics.reused, 0);
+}